@@ -5,7 +5,10 @@ use cosmwasm_std::{
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
 
 use crate::error::ContractError;
-use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO};
+use crate::state::{
+    decrease_balance, increase_balance, save_token_info, ALLOWANCES, ALLOWANCES_SPENDER, TOKEN_INFO,
+};
+use crate::tax::collect_tax;
 
 pub fn execute_increase_allowance(
     deps: DepsMut,
@@ -135,18 +138,9 @@ pub fn execute_transfer_from(
     // deduct allowance before doing anything else have enough allowance
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    decrease_balance(deps.storage, &owner_addr, amount, &env.block)?;
+    let tax = collect_tax(deps.storage, &env.block, &owner_addr, &rcpt_addr, amount)?;
+    increase_balance(deps.storage, &rcpt_addr, amount - tax, &env.block)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "transfer_from"),
@@ -154,6 +148,7 @@ pub fn execute_transfer_from(
         attr("to", recipient),
         attr("by", info.sender),
         attr("amount", amount),
+        attr("tax", tax),
     ]);
     Ok(res)
 }
@@ -172,18 +167,14 @@ pub fn execute_burn_from(
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     // lower balance
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    decrease_balance(deps.storage, &owner_addr, amount, &env.block)?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
-        meta.total_supply = meta.total_supply.checked_sub(amount)?;
-        Ok(meta)
-    })?;
+    let mut meta = TOKEN_INFO.load(deps.storage)?;
+    meta.total_supply = meta
+        .total_supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    save_token_info(deps.storage, &meta, env.block.height)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "burn_from"),
@@ -210,18 +201,13 @@ pub fn execute_send_from(
     deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &owner_addr,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    decrease_balance(deps.storage, &owner_addr, amount, &env.block)?;
+    let tax = collect_tax(deps.storage, &env.block, &owner_addr, &rcpt_addr, amount)?;
+    // the contract would be told it received nothing
+    if tax == amount {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    increase_balance(deps.storage, &rcpt_addr, amount - tax, &env.block)?;
 
     let attrs = vec![
         attr("action", "send_from"),
@@ -229,12 +215,13 @@ pub fn execute_send_from(
         attr("to", &contract),
         attr("by", &info.sender),
         attr("amount", amount),
+        attr("tax", tax),
     ];
 
     // create a send message
     let msg = Cw20ReceiveMsg {
         sender: info.sender.into(),
-        amount,
+        amount: amount - tax,
         msg,
     }
     .into_cosmos_msg(contract)?;
@@ -283,6 +270,8 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            initial_vesting: None,
+            tax: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
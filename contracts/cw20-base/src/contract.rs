@@ -2,7 +2,8 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::Order::Ascending;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Uint128,
 };
 
 use cw2::set_contract_version;
@@ -19,9 +20,21 @@ use crate::allowances::{
 use crate::enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances};
 use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::permit::{execute_permit, query_nonce};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::{query_balance_at_height, query_total_supply_at_height, start_snapshots};
+#[cfg(feature = "snapshot")]
+use crate::state::SNAPSHOT_START;
 use crate::state::{
-    MinterData, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, LOGO, MARKETING_INFO,
-    TOKEN_INFO,
+    decrease_balance, increase_balance, save_token_info, MinterData, TokenInfo, ALLOWANCES,
+    ALLOWANCES_SPENDER, BALANCES, LOGO, MARKETING_INFO, TOKEN_INFO,
+};
+use crate::tax::{
+    collect_tax, create_tax, execute_set_tax_exempt, execute_update_tax, query_tax_config,
+    query_tax_exempt,
+};
+use crate::vesting::{
+    create_vesting_accounts, execute_mint_with_vesting, query_spendable_balance, query_vesting_info,
 };
 
 // version info for migration info
@@ -94,7 +107,7 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -102,7 +115,10 @@ pub fn instantiate(
     // check valid token info
     msg.validate()?;
     // create initial accounts
-    let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
+    let mut total_supply = create_accounts(&mut deps, &env, &msg.initial_balances)?;
+    if let Some(vesting) = &msg.initial_vesting {
+        total_supply += create_vesting_accounts(&mut deps, &env, vesting)?;
+    }
 
     if let Some(limit) = msg.get_cap() {
         if total_supply > limit {
@@ -126,7 +142,13 @@ pub fn instantiate(
         total_supply,
         mint,
     };
-    TOKEN_INFO.save(deps.storage, &data)?;
+    save_token_info(deps.storage, &data, env.block.height)?;
+    #[cfg(feature = "snapshot")]
+    SNAPSHOT_START.save(deps.storage, &env.block.height)?;
+
+    if let Some(tax) = msg.tax {
+        create_tax(deps.storage, deps.api, tax)?;
+    }
 
     if let Some(marketing) = msg.marketing {
         let logo = if let Some(logo) = marketing.logo {
@@ -158,6 +180,7 @@ pub fn instantiate(
 
 pub fn create_accounts(
     deps: &mut DepsMut,
+    env: &Env,
     accounts: &[Cw20Coin],
 ) -> Result<Uint128, ContractError> {
     validate_accounts(accounts)?;
@@ -165,7 +188,7 @@ pub fn create_accounts(
     let mut total_supply = Uint128::zero();
     for row in accounts {
         let address = deps.api.addr_validate(&row.address)?;
-        BALANCES.save(deps.storage, &address, &row.amount)?;
+        increase_balance(deps.storage, &address, row.amount, &env.block)?;
         total_supply += row.amount;
     }
 
@@ -202,6 +225,11 @@ pub fn execute(
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
         ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+        ExecuteMsg::MintWithVesting {
+            recipient,
+            amount,
+            schedule,
+        } => execute_mint_with_vesting(deps, env, info, recipient, amount, schedule),
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -212,6 +240,16 @@ pub fn execute(
             amount,
             expires,
         } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
+        ExecuteMsg::Permit {
+            pubkey,
+            spender,
+            amount,
+            expires,
+            nonce,
+            signature,
+        } => execute_permit(
+            deps, env, pubkey, spender, amount, expires, nonce, signature,
+        ),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
@@ -230,6 +268,16 @@ pub fn execute(
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
+        ExecuteMsg::UpdateTax {
+            rate_bps,
+            min,
+            max,
+            collector,
+            admin,
+        } => execute_update_tax(deps, env, info, rate_bps, min, max, collector, admin),
+        ExecuteMsg::SetTaxExempt { address, exempt } => {
+            execute_set_tax_exempt(deps, env, info, address, exempt)
+        }
         ExecuteMsg::UpdateMinter { new_minter } => {
             execute_update_minter(deps, env, info, new_minter)
         }
@@ -238,7 +286,7 @@ pub fn execute(
 
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
@@ -249,30 +297,22 @@ pub fn execute_transfer(
 
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
 
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    decrease_balance(deps.storage, &info.sender, amount, &env.block)?;
+    let tax = collect_tax(deps.storage, &env.block, &info.sender, &rcpt_addr, amount)?;
+    increase_balance(deps.storage, &rcpt_addr, amount - tax, &env.block)?;
 
     let res = Response::new()
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_attribute("tax", tax);
     Ok(res)
 }
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
@@ -281,18 +321,14 @@ pub fn execute_burn(
     }
 
     // lower balance
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    decrease_balance(deps.storage, &info.sender, amount, &env.block)?;
     // reduce total_supply
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
-        info.total_supply = info.total_supply.checked_sub(amount)?;
-        Ok(info)
-    })?;
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    config.total_supply = config
+        .total_supply
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    save_token_info(deps.storage, &config, env.block.height)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -303,10 +339,22 @@ pub fn execute_burn(
 
 pub fn execute_mint(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
+) -> Result<Response, ContractError> {
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    mint(deps, env, info, &rcpt_addr, amount)
+}
+
+/// Mints `amount` to the already validated `rcpt_addr`, enforcing the minter and the cap
+pub(crate) fn mint(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    rcpt_addr: &Addr,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
@@ -333,26 +381,21 @@ pub fn execute_mint(
             return Err(ContractError::CannotExceedCap {});
         }
     }
-    TOKEN_INFO.save(deps.storage, &config)?;
+    save_token_info(deps.storage, &config, env.block.height)?;
 
     // add amount to recipient balance
-    let rcpt_addr = deps.api.addr_validate(&recipient)?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    increase_balance(deps.storage, rcpt_addr, amount, &env.block)?;
 
     let res = Response::new()
         .add_attribute("action", "mint")
-        .add_attribute("to", recipient)
+        .add_attribute("to", rcpt_addr)
         .add_attribute("amount", amount);
     Ok(res)
 }
 
 pub fn execute_send(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     contract: String,
     amount: Uint128,
@@ -365,28 +408,24 @@ pub fn execute_send(
     let rcpt_addr = deps.api.addr_validate(&contract)?;
 
     // move the tokens to the contract
-    BALANCES.update(
-        deps.storage,
-        &info.sender,
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        &rcpt_addr,
-        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
-    )?;
+    decrease_balance(deps.storage, &info.sender, amount, &env.block)?;
+    let tax = collect_tax(deps.storage, &env.block, &info.sender, &rcpt_addr, amount)?;
+    // the contract would be told it received nothing
+    if tax == amount {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    increase_balance(deps.storage, &rcpt_addr, amount - tax, &env.block)?;
 
     let res = Response::new()
         .add_attribute("action", "send")
         .add_attribute("from", &info.sender)
         .add_attribute("to", &contract)
         .add_attribute("amount", amount)
+        .add_attribute("tax", tax)
         .add_message(
             Cw20ReceiveMsg {
                 sender: info.sender.into(),
-                amount,
+                amount: amount - tax,
                 msg,
             }
             .into_cosmos_msg(contract)?,
@@ -521,7 +560,7 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
         QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
@@ -529,6 +568,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::Nonce { owner } => to_binary(&query_nonce(deps, owner)?),
         QueryMsg::AllAllowances {
             owner,
             start_after,
@@ -549,6 +589,20 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
+        QueryMsg::TaxConfig {} => to_binary(&query_tax_config(deps)?),
+        QueryMsg::TaxExempt { address } => to_binary(&query_tax_exempt(deps, address)?),
+        QueryMsg::VestingInfo { address } => to_binary(&query_vesting_info(deps, env, address)?),
+        QueryMsg::SpendableBalance { address } => {
+            to_binary(&query_spendable_balance(deps, env, address)?)
+        }
+        #[cfg(feature = "snapshot")]
+        QueryMsg::BalanceAtHeight { address, height } => {
+            to_binary(&query_balance_at_height(deps, address, height)?)
+        }
+        #[cfg(feature = "snapshot")]
+        QueryMsg::TotalSupplyAtHeight { height } => {
+            to_binary(&query_total_supply_at_height(deps, height)?)
+        }
     }
 }
 
@@ -603,7 +657,7 @@ pub fn query_download_logo(deps: Deps) -> StdResult<DownloadLogoResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let original_version =
         ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -616,6 +670,13 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
             ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
         }
     }
+
+    // start recording balances if the previous code did not
+    #[cfg(feature = "snapshot")]
+    start_snapshots(deps.storage, env.block.height)?;
+    #[cfg(not(feature = "snapshot"))]
+    let _ = env;
+
     Ok(Response::default())
 }
 
@@ -674,6 +735,8 @@ mod tests {
             }],
             mint: mint.clone(),
             marketing: None,
+            initial_vesting: None,
+            tax: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -714,6 +777,8 @@ mod tests {
                 }],
                 mint: None,
                 marketing: None,
+                initial_vesting: None,
+                tax: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -754,6 +819,8 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                initial_vesting: None,
+                tax: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -801,6 +868,8 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                initial_vesting: None,
+                tax: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -829,6 +898,8 @@ mod tests {
                         marketing: Some("marketing".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    initial_vesting: None,
+                    tax: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -869,6 +940,8 @@ mod tests {
                         marketing: Some("m".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    initial_vesting: None,
+                    tax: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -1085,6 +1158,8 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            initial_vesting: None,
+            tax: None,
         };
         let err =
             instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap_err();
@@ -1107,6 +1182,8 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            initial_vesting: None,
+            tax: None,
         };
         let res = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap();
         assert_eq!(0, res.messages.len());
@@ -1380,6 +1457,8 @@ mod tests {
                         }],
                         mint: None,
                         marketing: None,
+                        initial_vesting: None,
+                        tax: None,
                     },
                     &[],
                     "TOKEN",
@@ -1481,6 +1560,8 @@ mod tests {
                     marketing: Some("marketing".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1535,6 +1616,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1588,6 +1671,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1641,6 +1726,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1694,6 +1781,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1747,6 +1836,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1800,6 +1891,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1857,6 +1950,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1910,6 +2005,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1959,6 +2056,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2009,6 +2108,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2060,6 +2161,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2110,6 +2213,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2167,6 +2272,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2217,6 +2324,8 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                initial_vesting: None,
+                tax: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -101,6 +101,8 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            initial_vesting: None,
+            tax: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
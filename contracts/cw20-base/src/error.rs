@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -36,6 +36,30 @@ pub enum ContractError {
     #[error("Invalid expiration value")]
     InvalidExpiration {},
 
+    #[error("Cannot spend {locked} tokens that are still vesting")]
+    LockedBalance { locked: Uint128 },
+
+    #[error("Cannot hold more than {max} vesting schedules")]
+    TooManyVestingSchedules { max: u32 },
+
+    #[error("Permit is expired")]
+    PermitExpired {},
+
+    #[error("Invalid permit nonce, expected {expected}")]
+    InvalidNonce { expected: u64 },
+
+    #[error("Invalid permit public key")]
+    InvalidPubkey {},
+
+    #[error("Invalid permit signature")]
+    InvalidSignature {},
+
+    #[error("Tax rate cannot exceed 10000 basis points")]
+    InvalidTaxRate {},
+
+    #[error("Maximum tax cannot be lower than the minimum")]
+    InvalidTaxCaps {},
+
     #[error("Duplicate initial balance addresses")]
     DuplicateInitialBalanceAddresses {},
 }
@@ -8,7 +8,10 @@ Implements:
 
 - [x] CW20 Base
 - [x] Mintable extension
-- [x] Allowances extension
+- [x] Allowances extension, with signed permits
+- [x] Balance snapshots, with the "snapshot" feature
+- [x] Vesting schedules for initial balances and mints
+- [x] Transfer tax, credited to a fee collector
 
 For more information on this contract, please check out the
 [README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-base/README.md).
@@ -19,6 +22,11 @@ pub mod contract;
 pub mod enumerable;
 mod error;
 pub mod msg;
+pub mod permit;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
 pub mod state;
+pub mod tax;
+pub mod vesting;
 
 pub use crate::error::ContractError;
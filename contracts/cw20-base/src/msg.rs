@@ -1,10 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{StdError, StdResult, Uint128};
-use cw20::{Cw20Coin, Logo, MinterResponse};
+use cosmwasm_std::{Binary, BlockInfo, StdError, StdResult, Timestamp, Uint128};
+use cw20::{Cw20Coin, Expiration, Logo, MinterResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub use cw20::Cw20ExecuteMsg as ExecuteMsg;
+use crate::state::{TaxConfig, Vesting};
 
 #[cw_serde]
 pub struct InstantiateMarketingInfo {
@@ -23,6 +23,84 @@ pub struct InstantiateMsg {
     pub initial_balances: Vec<Cw20Coin>,
     pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
+    /// Balances released by a vesting schedule, in addition to `initial_balances`
+    pub initial_vesting: Option<Vec<VestingCoin>>,
+    /// Tax deducted from transfers and sends, the token is untaxed if None
+    pub tax: Option<InstantiateTaxInfo>,
+}
+
+#[cw_serde]
+pub struct InstantiateTaxInfo {
+    /// Tax on transfers and sends, in basis points of the amount
+    pub rate_bps: u16,
+    /// Lowest tax per transfer, the tax never exceeds the amount
+    pub min: Uint128,
+    /// Highest tax per transfer, unlimited if None
+    pub max: Option<Uint128>,
+    /// The address the tax is credited to
+    pub collector: String,
+    /// The address that can update the tax
+    pub admin: String,
+    /// Transfers from or to these addresses are not taxed, the collector is always exempt
+    pub exempt: Vec<String>,
+}
+
+#[cw_serde]
+pub struct VestingCoin {
+    pub address: String,
+    pub amount: Uint128,
+    pub schedule: VestingSchedule,
+}
+
+/// Vesting tokens are released linearly from `start` to `end`, but none before `cliff`.
+/// Until released they count towards the balance but cannot be transferred, sent or burned.
+#[cw_serde]
+pub enum VestingSchedule {
+    /// Vesting by block height
+    Height { start: u64, cliff: u64, end: u64 },
+    /// Vesting by block time
+    Time {
+        start: Timestamp,
+        cliff: Timestamp,
+        end: Timestamp,
+    },
+}
+
+impl VestingSchedule {
+    pub fn validate(&self) -> StdResult<()> {
+        let (start, cliff, end) = self.points();
+        if start > cliff || cliff > end {
+            return Err(StdError::generic_err(
+                "Vesting schedule must satisfy start <= cliff <= end",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns how much of `amount` is released at `block`
+    pub fn vested(&self, amount: Uint128, block: &BlockInfo) -> Uint128 {
+        let (start, cliff, end) = self.points();
+        let now = match self {
+            VestingSchedule::Height { .. } => block.height,
+            VestingSchedule::Time { .. } => block.time.nanos(),
+        };
+        if now < cliff {
+            Uint128::zero()
+        } else if now >= end {
+            amount
+        } else {
+            amount.multiply_ratio(now - start, end - start)
+        }
+    }
+
+    pub(crate) fn points(&self) -> (u64, u64, u64) {
+        match self {
+            VestingSchedule::Height { start, cliff, end } => (*start, *cliff, *end),
+            VestingSchedule::Time { start, cliff, end } => {
+                (start.nanos(), cliff.nanos(), end.nanos())
+            }
+        }
+    }
 }
 
 impl InstantiateMsg {
@@ -70,6 +148,120 @@ impl InstantiateMsg {
     }
 }
 
+/// The cw20 execute messages, extended with vesting mints
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move tokens to another account without triggering actions
+    Transfer { recipient: String, amount: Uint128 },
+    /// Burn is a base message to destroy tokens forever
+    Burn { amount: Uint128 },
+    /// Send is a base message to transfer tokens to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Allows spender to access an additional amount tokens
+    /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
+    /// expiration with this one.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Lowers the spender's access of tokens
+    /// from the owner's (env.sender) account by amount. If expires is Some(), overwrites current
+    /// allowance expiration with this one.
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Sets the allowance of spender on the account of the
+    /// owner of `pubkey` to amount, until `expires`. Instead of a transaction of the owner it
+    /// needs their signature over the `PermitPayload`, so anyone can submit it.
+    Permit {
+        /// The compressed secp256k1 public key of the owner
+        pubkey: Binary,
+        spender: String,
+        amount: Uint128,
+        expires: Expiration,
+        /// Must be the current nonce of the owner, see `QueryMsg::Nonce`
+        nonce: u64,
+        signature: Binary,
+    },
+    /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
+    /// if `env.sender` has sufficient pre-approval.
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Only with "approval" extension. Sends amount tokens from owner -> contract
+    /// if `env.sender` has sufficient pre-approval.
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Destroys tokens forever
+    BurnFrom { owner: String, amount: Uint128 },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// for the recipient that are released by the vesting schedule.
+    MintWithVesting {
+        recipient: String,
+        amount: Uint128,
+        schedule: VestingSchedule,
+    },
+    /// Only with the "mintable" extension. The current minter may set
+    /// a new minter. Setting the minter to None will remove the
+    /// token's minter forever.
+    UpdateMinter { new_minter: Option<String> },
+    /// Only with the "marketing" extension. If authorized, updates marketing metadata.
+    /// Setting None/null for any of these will leave it unchanged.
+    /// Setting Some("") will clear this field on the contract storage
+    UpdateMarketing {
+        /// A URL pointing to the project behind this token.
+        project: Option<String>,
+        /// A longer description of the token and it's utility. Designed for tooltips or such
+        description: Option<String>,
+        /// The address (if any) who can update this data structure
+        marketing: Option<String>,
+    },
+    /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
+    UploadLogo(Logo),
+    /// Only for tokens instantiated with a tax. If authorized, replaces the tax on transfers
+    /// and sends. A rate and minimum of 0 disable the tax.
+    UpdateTax {
+        rate_bps: u16,
+        min: Uint128,
+        max: Option<Uint128>,
+        collector: String,
+        admin: String,
+    },
+    /// Only for tokens instantiated with a tax. If authorized, sets whether transfers from or
+    /// to address are taxed.
+    SetTaxExempt { address: String, exempt: bool },
+}
+
+/// The message signed by the owner for a `Permit`. The signature is over the sha256 hash of
+/// its JSON encoding, with the fields in this order.
+#[cw_serde]
+pub struct PermitPayload {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub spender: String,
+    pub amount: Uint128,
+    pub expires: Expiration,
+    pub nonce: u64,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -87,6 +279,10 @@ pub enum QueryMsg {
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(cw20::AllowanceResponse)]
     Allowance { owner: String, spender: String },
+    /// Only with "allowance" extension.
+    /// Returns the nonce the next permit of owner must use, 0 if unset.
+    #[returns(NonceResponse)]
+    Nonce { owner: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     #[returns(cw20::AllAllowancesResponse)]
@@ -120,6 +316,55 @@ pub enum QueryMsg {
     /// contract.
     #[returns(cw20::DownloadLogoResponse)]
     DownloadLogo {},
+    /// Returns the tax on transfers and sends, None if the token has no tax.
+    #[returns(TaxConfigResponse)]
+    TaxConfig {},
+    /// Returns whether transfers from or to the given address are not taxed.
+    #[returns(TaxExemptResponse)]
+    TaxExempt { address: String },
+    /// Returns the vesting balances of the given address and how much of them is still locked.
+    #[returns(VestingInfoResponse)]
+    VestingInfo { address: String },
+    /// Returns the part of the balance of the given address that is not locked by vesting,
+    /// 0 if unset.
+    #[returns(cw20::BalanceResponse)]
+    SpendableBalance { address: String },
+    /// Only with "snapshot" feature
+    /// Returns the balance of the given address at the start of block `height`, 0 if unset.
+    #[cfg(feature = "snapshot")]
+    #[returns(cw20::BalanceResponse)]
+    BalanceAtHeight { address: String, height: u64 },
+    /// Only with "snapshot" feature
+    /// Returns the total supply at the start of block `height`.
+    #[cfg(feature = "snapshot")]
+    #[returns(TotalSupplyResponse)]
+    TotalSupplyAtHeight { height: u64 },
+}
+
+#[cw_serde]
+pub struct NonceResponse {
+    pub nonce: u64,
+}
+
+#[cw_serde]
+pub struct TaxConfigResponse {
+    pub tax: Option<TaxConfig>,
+}
+
+#[cw_serde]
+pub struct TaxExemptResponse {
+    pub exempt: bool,
+}
+
+#[cw_serde]
+pub struct VestingInfoResponse {
+    pub vesting: Vec<Vesting>,
+    pub locked: Uint128,
+}
+
+#[cw_serde]
+pub struct TotalSupplyResponse {
+    pub total_supply: Uint128,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
@@ -0,0 +1,303 @@
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{
+    attr, to_vec, Addr, Api, Binary, Deps, DepsMut, Env, Response, StdError, StdResult, Uint128,
+};
+use cw20::{AllowanceResponse, Expiration};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::error::ContractError;
+use crate::msg::{NonceResponse, PermitPayload};
+use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, PERMIT_NONCES};
+
+/// Derives the account address of a compressed secp256k1 public key, like the chain does.
+/// The bech32 prefix is the one of the contract address.
+fn pubkey_address(api: &dyn Api, contract: &Addr, pubkey: &[u8]) -> Result<Addr, ContractError> {
+    if pubkey.len() != 33 {
+        return Err(ContractError::InvalidPubkey {});
+    }
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    let (prefix, _, _) = bech32::decode(contract.as_str())
+        .map_err(|err| StdError::generic_err(format!("Invalid contract address: {}", err)))?;
+    let address = bech32::encode(&prefix, hash.to_base32(), Variant::Bech32)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(api.addr_validate(&address)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    pubkey: Binary,
+    spender: String,
+    amount: Uint128,
+    expires: Expiration,
+    nonce: u64,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::PermitExpired {});
+    }
+    let owner = pubkey_address(deps.api, &env.contract.address, &pubkey)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == owner {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    // every nonce can only be used once, in order
+    let expected = PERMIT_NONCES
+        .may_load(deps.storage, &owner)?
+        .unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::InvalidNonce { expected });
+    }
+
+    let payload = PermitPayload {
+        chain_id: env.block.chain_id,
+        contract: env.contract.address.into_string(),
+        owner: owner.to_string(),
+        spender: spender.clone(),
+        amount,
+        expires,
+        nonce,
+    };
+    let hash = Sha256::digest(to_vec(&payload)?);
+    if !matches!(
+        deps.api.secp256k1_verify(&hash, &signature, &pubkey),
+        Ok(true)
+    ) {
+        return Err(ContractError::InvalidSignature {});
+    }
+    PERMIT_NONCES.save(deps.storage, &owner, &(nonce + 1))?;
+
+    if amount.is_zero() {
+        ALLOWANCES.remove(deps.storage, (&owner, &spender_addr));
+        ALLOWANCES_SPENDER.remove(deps.storage, (&spender_addr, &owner));
+    } else {
+        let allowance = AllowanceResponse {
+            allowance: amount,
+            expires,
+        };
+        ALLOWANCES.save(deps.storage, (&owner, &spender_addr), &allowance)?;
+        ALLOWANCES_SPENDER.save(deps.storage, (&spender_addr, &owner), &allowance)?;
+    }
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "permit"),
+        attr("owner", owner),
+        attr("spender", spender),
+        attr("amount", amount),
+        attr("nonce", nonce.to_string()),
+    ]);
+    Ok(res)
+}
+
+pub fn query_nonce(deps: Deps, owner: String) -> StdResult<NonceResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let nonce = PERMIT_NONCES
+        .may_load(deps.storage, &owner)?
+        .unwrap_or_default();
+    Ok(NonceResponse { nonce })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cw20::Cw20Coin;
+    use k256::ecdsa::signature::Signer;
+    use k256::ecdsa::{Signature, SigningKey};
+
+    use crate::allowances::query_allowance;
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    fn test_env() -> Env {
+        let mut env = mock_env();
+        let contract = bech32::encode("wasm", [7u8; 32].to_base32(), Variant::Bech32).unwrap();
+        env.contract.address = Addr::unchecked(contract);
+        env
+    }
+
+    fn owner_of(env: &Env, key: &SigningKey) -> Addr {
+        let deps = mock_dependencies();
+        let pubkey = key.verifying_key().to_bytes();
+        pubkey_address(&deps.api, &env.contract.address, &pubkey).unwrap()
+    }
+
+    fn sign_permit(
+        env: &Env,
+        key: &SigningKey,
+        spender: &str,
+        amount: u128,
+        nonce: u64,
+    ) -> ExecuteMsg {
+        let expires = Expiration::AtHeight(env.block.height + 100);
+        let payload = PermitPayload {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            owner: owner_of(env, key).to_string(),
+            spender: spender.to_string(),
+            amount: Uint128::new(amount),
+            expires,
+            nonce,
+        };
+        // signs the sha256 hash of the payload
+        let signature: Signature = key.sign(&to_vec(&payload).unwrap());
+        ExecuteMsg::Permit {
+            pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+            spender: spender.to_string(),
+            amount: Uint128::new(amount),
+            expires,
+            nonce,
+            signature: Binary::from(signature.as_ref()),
+        }
+    }
+
+    #[test]
+    fn derives_chain_address() {
+        // the address of this key on the cosmos hub
+        let pubkey = Binary::from_base64("AtQaCqFnshaZQp6rIkvAPyzThvCvXSDO+9AzbxVErqJP").unwrap();
+        let deps = mock_dependencies();
+        let contract = bech32::encode("cosmos", [7u8; 32].to_base32(), Variant::Bech32).unwrap();
+        let address = pubkey_address(&deps.api, &Addr::unchecked(contract), &pubkey).unwrap();
+        assert_eq!(
+            address.as_str(),
+            "cosmos1h806c7khnvmjlywdrkdgk2vrayy2mmvf9rxk2r"
+        );
+    }
+
+    #[test]
+    fn permit_sets_allowance() {
+        let mut deps = mock_dependencies();
+        let env = test_env();
+        let key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+        let owner = owner_of(&env, &key);
+
+        let instantiate_msg = InstantiateMsg {
+            name: "Permit Coin".to_string(),
+            symbol: "PERM".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: owner.to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: None,
+            marketing: None,
+            initial_vesting: None,
+            tax: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+        // anyone can submit the permit
+        let msg = sign_permit(&env, &key, "spender", 300, 0);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("relayer", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        let allowance =
+            query_allowance(deps.as_ref(), owner.to_string(), "spender".to_string()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(300));
+        assert_eq!(
+            allowance.expires,
+            Expiration::AtHeight(env.block.height + 100)
+        );
+        assert_eq!(
+            query_nonce(deps.as_ref(), owner.to_string()).unwrap().nonce,
+            1
+        );
+
+        // a permit cannot be replayed
+        let err = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidNonce { expected: 1 });
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: owner.to_string(),
+            recipient: "other".to_string(),
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("spender", &[]), msg).unwrap();
+        assert_eq!(
+            query_balance(deps.as_ref(), owner.to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(700)
+        );
+
+        // the next permit replaces the allowance
+        let msg = sign_permit(&env, &key, "spender", 0, 1);
+        execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap();
+        let allowance =
+            query_allowance(deps.as_ref(), owner.to_string(), "spender".to_string()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+    }
+
+    #[test]
+    fn invalid_permits() {
+        let mut deps = mock_dependencies();
+        let mut env = test_env();
+        let key = SigningKey::from_bytes(&[1u8; 32]).unwrap();
+        let other_key = SigningKey::from_bytes(&[2u8; 32]).unwrap();
+
+        // signed by another key
+        let msg = match sign_permit(&env, &other_key, "spender", 300, 0) {
+            ExecuteMsg::Permit {
+                spender,
+                amount,
+                expires,
+                nonce,
+                signature,
+                ..
+            } => ExecuteMsg::Permit {
+                pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+                spender,
+                amount,
+                expires,
+                nonce,
+                signature,
+            },
+            _ => unreachable!(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // signed for a different amount
+        let msg = match sign_permit(&env, &key, "spender", 300, 0) {
+            ExecuteMsg::Permit {
+                pubkey,
+                spender,
+                expires,
+                nonce,
+                signature,
+                ..
+            } => ExecuteMsg::Permit {
+                pubkey,
+                spender,
+                amount: Uint128::new(3000),
+                expires,
+                nonce,
+                signature,
+            },
+            _ => unreachable!(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // for the owner itself
+        let owner = owner_of(&env, &key);
+        let msg = sign_permit(&env, &key, owner.as_str(), 300, 0);
+        let err = execute(deps.as_mut(), env.clone(), mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotSetOwnAccount {});
+
+        // expired
+        let msg = sign_permit(&env, &key, "spender", 300, 0);
+        env.block.height += 100;
+        let err = execute(deps.as_mut(), env, mock_info("relayer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PermitExpired {});
+    }
+}
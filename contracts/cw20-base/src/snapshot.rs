@@ -0,0 +1,229 @@
+use cosmwasm_std::{Deps, StdError, StdResult, Storage};
+use cw20::BalanceResponse;
+
+use crate::msg::TotalSupplyResponse;
+use crate::state::{BALANCES, SNAPSHOT_START, TOKEN_INFO, TOTAL_SUPPLY};
+
+/// Starts recording the total supply for contracts instantiated without snapshots.
+/// Balances need no migration, their changes are recorded from now on.
+pub fn start_snapshots(storage: &mut dyn Storage, height: u64) -> StdResult<()> {
+    if SNAPSHOT_START.may_load(storage)?.is_some() {
+        return Ok(());
+    }
+    let info = TOKEN_INFO.load(storage)?;
+    TOTAL_SUPPLY.save(storage, &info.total_supply, height)?;
+    // changes made earlier in this block were not recorded
+    SNAPSHOT_START.save(storage, &(height + 1))
+}
+
+fn ensure_recorded(storage: &dyn Storage, height: u64) -> StdResult<()> {
+    let start = SNAPSHOT_START.load(storage)?;
+    if height < start {
+        return Err(StdError::generic_err(format!(
+            "Snapshots are only available from height {}",
+            start
+        )));
+    }
+    Ok(())
+}
+
+pub fn query_balance_at_height(
+    deps: Deps,
+    address: String,
+    height: u64,
+) -> StdResult<BalanceResponse> {
+    ensure_recorded(deps.storage, height)?;
+    let address = deps.api.addr_validate(&address)?;
+    let balance = BALANCES
+        .may_load_at_height(deps.storage, &address, height)?
+        .unwrap_or_default();
+    Ok(BalanceResponse { balance })
+}
+
+pub fn query_total_supply_at_height(deps: Deps, height: u64) -> StdResult<TotalSupplyResponse> {
+    ensure_recorded(deps.storage, height)?;
+    let total_supply = TOTAL_SUPPLY
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+    Ok(TotalSupplyResponse { total_supply })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Addr, Uint128};
+    use cw20::{Cw20Coin, MinterResponse};
+    use cw_storage_plus::Map;
+
+    use crate::contract::{execute, instantiate, migrate};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
+    use crate::state::TokenInfo;
+
+    fn get_balance_at(deps: Deps, address: &str, height: u64) -> Uint128 {
+        query_balance_at_height(deps, address.to_string(), height)
+            .unwrap()
+            .balance
+    }
+
+    fn get_total_supply_at(deps: Deps, height: u64) -> Uint128 {
+        query_total_supply_at_height(deps, height)
+            .unwrap()
+            .total_supply
+    }
+
+    #[test]
+    fn balances_at_height() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start = env.block.height;
+
+        let instantiate_msg = InstantiateMsg {
+            name: "Snapshot Coin".to_string(),
+            symbol: "SNAP".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "owner".to_string(),
+                amount: Uint128::new(1000),
+            }],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            initial_vesting: None,
+            tax: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+        env.block.height = start + 10;
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(300),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        env.block.height = start + 20;
+        let msg = ExecuteMsg::Mint {
+            recipient: "other".to_string(),
+            amount: Uint128::new(50),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("minter", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+
+        // heights refer to the start of the block, before its changes
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start),
+            Uint128::zero()
+        );
+        assert_eq!(get_total_supply_at(deps.as_ref(), start), Uint128::zero());
+
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 10),
+            Uint128::new(1000)
+        );
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "other", start + 10),
+            Uint128::zero()
+        );
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 11),
+            Uint128::new(700)
+        );
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "other", start + 11),
+            Uint128::new(300)
+        );
+        assert_eq!(
+            get_total_supply_at(deps.as_ref(), start + 20),
+            Uint128::new(1000)
+        );
+
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 21),
+            Uint128::new(600)
+        );
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "other", start + 21),
+            Uint128::new(350)
+        );
+        assert_eq!(
+            get_total_supply_at(deps.as_ref(), start + 21),
+            Uint128::new(950)
+        );
+
+        // nothing is recorded before instantiation
+        let err = query_balance_at_height(deps.as_ref(), "owner".to_string(), start - 1);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn migrate_without_snapshots() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start = env.block.height;
+
+        // state written by a version without the "snapshot" feature
+        cw2::set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.15.0").unwrap();
+        let balances: Map<&Addr, Uint128> = Map::new("balance");
+        balances
+            .save(
+                &mut deps.storage,
+                &Addr::unchecked("owner"),
+                &Uint128::new(500),
+            )
+            .unwrap();
+        let info = TokenInfo {
+            name: "Snapshot Coin".to_string(),
+            symbol: "SNAP".to_string(),
+            decimals: 6,
+            total_supply: Uint128::new(500),
+            mint: None,
+        };
+        TOKEN_INFO.save(&mut deps.storage, &info).unwrap();
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        // only heights after the migration can be queried
+        let err = query_total_supply_at_height(deps.as_ref(), start);
+        assert!(err.is_err());
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 1),
+            Uint128::new(500)
+        );
+        assert_eq!(
+            get_total_supply_at(deps.as_ref(), start + 1),
+            Uint128::new(500)
+        );
+
+        env.block.height = start + 5;
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(200),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 5),
+            Uint128::new(500)
+        );
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 6),
+            Uint128::new(300)
+        );
+        assert_eq!(
+            get_total_supply_at(deps.as_ref(), start + 6),
+            Uint128::new(300)
+        );
+
+        // migrating again keeps the recorded history
+        migrate(deps.as_mut(), env, MigrateMsg {}).unwrap();
+        assert_eq!(
+            get_balance_at(deps.as_ref(), "owner", start + 5),
+            Uint128::new(500)
+        );
+    }
+}
@@ -1,9 +1,14 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, BlockInfo, StdError, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
+#[cfg(feature = "snapshot")]
+use cw_storage_plus::{SnapshotItem, SnapshotMap, Strategy};
 
 use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
 
+use crate::msg::VestingSchedule;
+use crate::ContractError;
+
 #[cw_serde]
 pub struct TokenInfo {
     pub name: String,
@@ -26,11 +31,160 @@ impl TokenInfo {
     }
 }
 
+#[cw_serde]
+pub struct TaxConfig {
+    /// Tax on transfers and sends, in basis points of the amount
+    pub rate_bps: u16,
+    /// Lowest tax per transfer, the tax never exceeds the amount
+    pub min: Uint128,
+    /// Highest tax per transfer, unlimited if None
+    pub max: Option<Uint128>,
+    pub collector: Addr,
+    pub admin: Addr,
+}
+
+impl TaxConfig {
+    /// The tax on moving `amount` between addresses that are not exempt
+    pub fn tax(&self, amount: Uint128) -> Uint128 {
+        let mut tax = amount
+            .multiply_ratio(self.rate_bps, 10_000u128)
+            .max(self.min);
+        if let Some(max) = self.max {
+            tax = tax.min(max);
+        }
+        tax.min(amount)
+    }
+}
+
+#[cw_serde]
+pub struct Vesting {
+    pub amount: Uint128,
+    pub schedule: VestingSchedule,
+}
+
+impl Vesting {
+    /// How much of the vesting amount is not released yet at `block`
+    pub fn locked(&self, block: &BlockInfo) -> Uint128 {
+        self.amount - self.schedule.vested(self.amount, block)
+    }
+}
+
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
+/// Only set for tokens instantiated with a tax
+pub const TAX_CONFIG: Item<TaxConfig> = Item::new("tax_config");
+/// Transfers from or to these addresses are not taxed, next to the collector
+pub const TAX_EXEMPT: Map<&Addr, ()> = Map::new("tax_exempt");
+#[cfg(not(feature = "snapshot"))]
 pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+/// With the "snapshot" feature balances are also recorded per block height.
+/// Current balances stay under the same key, so existing data can be read after migrating.
+#[cfg(feature = "snapshot")]
+pub const BALANCES: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "balance",
+    "balance__checkpoints",
+    "balance__changelog",
+    Strategy::EveryBlock,
+);
+/// Only with the "snapshot" feature, the total supply per block height.
+#[cfg(feature = "snapshot")]
+pub const TOTAL_SUPPLY: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_supply",
+    "total_supply__checkpoints",
+    "total_supply__changelog",
+    Strategy::EveryBlock,
+);
+/// Only with the "snapshot" feature, the first height that can be queried.
+#[cfg(feature = "snapshot")]
+pub const SNAPSHOT_START: Item<u64> = Item::new("snapshot_start");
+/// Vesting balances of each account, they are part of `BALANCES` but locked until released
+pub const VESTING: Map<&Addr, Vec<Vesting>> = Map::new("vesting");
+/// The nonce the next permit of each owner must use
+pub const PERMIT_NONCES: Map<&Addr, u64> = Map::new("permit_nonce");
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 // TODO: After https://github.com/CosmWasm/cw-plus/issues/670 is implemented, replace this with a `MultiIndex` over `ALLOWANCES`
 pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
     Map::new("allowance_spender");
+
+/// Adds `amount` to the balance of `address` in `block`
+pub fn increase_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+    block: &BlockInfo,
+) -> StdResult<Uint128> {
+    update_balance(storage, address, block.height, |balance| {
+        Ok(balance.unwrap_or_default() + amount)
+    })
+}
+
+/// Removes `amount` from the balance of `address` in `block`,
+/// fails if it is too low or would spend tokens that are still vesting
+pub fn decrease_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    amount: Uint128,
+    block: &BlockInfo,
+) -> Result<Uint128, ContractError> {
+    let balance = BALANCES
+        .may_load(storage, address)?
+        .unwrap_or_default()
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    let locked = locked_balance(storage, address, block)?;
+    if balance < locked {
+        return Err(ContractError::LockedBalance { locked });
+    }
+    update_balance(storage, address, block.height, |_| Ok(balance))?;
+    Ok(balance)
+}
+
+/// The part of the balance of `address` that is still vesting in `block`
+pub fn locked_balance(
+    storage: &dyn Storage,
+    address: &Addr,
+    block: &BlockInfo,
+) -> StdResult<Uint128> {
+    let vesting = VESTING.may_load(storage, address)?.unwrap_or_default();
+    Ok(vesting.iter().map(|v| v.locked(block)).sum())
+}
+
+#[cfg(not(feature = "snapshot"))]
+fn update_balance<A>(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    _height: u64,
+    action: A,
+) -> StdResult<Uint128>
+where
+    A: FnOnce(Option<Uint128>) -> StdResult<Uint128>,
+{
+    BALANCES.update(storage, address, action)
+}
+
+#[cfg(feature = "snapshot")]
+fn update_balance<A>(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    height: u64,
+    action: A,
+) -> StdResult<Uint128>
+where
+    A: FnOnce(Option<Uint128>) -> StdResult<Uint128>,
+{
+    BALANCES.update(storage, address, height, action)
+}
+
+/// Saves the token info, with the "snapshot" feature also the total supply at block `height`
+#[cfg(not(feature = "snapshot"))]
+pub fn save_token_info(storage: &mut dyn Storage, info: &TokenInfo, _height: u64) -> StdResult<()> {
+    TOKEN_INFO.save(storage, info)
+}
+
+/// Saves the token info, with the "snapshot" feature also the total supply at block `height`
+#[cfg(feature = "snapshot")]
+pub fn save_token_info(storage: &mut dyn Storage, info: &TokenInfo, height: u64) -> StdResult<()> {
+    TOTAL_SUPPLY.save(storage, &info.total_supply, height)?;
+    TOKEN_INFO.save(storage, info)
+}
@@ -0,0 +1,430 @@
+use cosmwasm_std::{
+    Addr, Api, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::msg::{InstantiateTaxInfo, TaxConfigResponse, TaxExemptResponse};
+use crate::state::{increase_balance, TaxConfig, TAX_CONFIG, TAX_EXEMPT};
+
+fn validate_tax(config: &TaxConfig) -> Result<(), ContractError> {
+    if config.rate_bps > 10_000 {
+        return Err(ContractError::InvalidTaxRate {});
+    }
+    if matches!(config.max, Some(max) if max < config.min) {
+        return Err(ContractError::InvalidTaxCaps {});
+    }
+    Ok(())
+}
+
+/// Saves the tax of a new token
+pub fn create_tax(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    tax: InstantiateTaxInfo,
+) -> Result<(), ContractError> {
+    let config = TaxConfig {
+        rate_bps: tax.rate_bps,
+        min: tax.min,
+        max: tax.max,
+        collector: api.addr_validate(&tax.collector)?,
+        admin: api.addr_validate(&tax.admin)?,
+    };
+    validate_tax(&config)?;
+    TAX_CONFIG.save(storage, &config)?;
+    for address in tax.exempt {
+        TAX_EXEMPT.save(storage, &api.addr_validate(&address)?, &())?;
+    }
+    Ok(())
+}
+
+/// Credits the tax on moving `amount` from `from` to `to` to the collector and returns it.
+/// The recipient gets the rest. Transfers from or to the collector are not taxed, so its
+/// balance is not taxed back to itself.
+pub fn collect_tax(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    let tax = match TAX_CONFIG.may_load(storage)? {
+        Some(config) => {
+            let exempt = [from, to]
+                .iter()
+                .any(|address| **address == config.collector || TAX_EXEMPT.has(storage, address));
+            if exempt {
+                return Ok(Uint128::zero());
+            }
+            let tax = config.tax(amount);
+            if !tax.is_zero() {
+                increase_balance(storage, &config.collector, tax, block)?;
+            }
+            tax
+        }
+        None => Uint128::zero(),
+    };
+    Ok(tax)
+}
+
+fn load_as_admin(deps: Deps, info: &MessageInfo) -> Result<TaxConfig, ContractError> {
+    let config = TAX_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    if config.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(config)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_update_tax(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    rate_bps: u16,
+    min: Uint128,
+    max: Option<Uint128>,
+    collector: String,
+    admin: String,
+) -> Result<Response, ContractError> {
+    let mut config = load_as_admin(deps.as_ref(), &info)?;
+
+    config.rate_bps = rate_bps;
+    config.min = min;
+    config.max = max;
+    config.collector = deps.api.addr_validate(&collector)?;
+    config.admin = deps.api.addr_validate(&admin)?;
+    validate_tax(&config)?;
+    TAX_CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_tax")
+        .add_attribute("rate_bps", rate_bps.to_string())
+        .add_attribute("collector", config.collector)
+        .add_attribute("admin", config.admin);
+    Ok(res)
+}
+
+pub fn execute_set_tax_exempt(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    load_as_admin(deps.as_ref(), &info)?;
+
+    let addr = deps.api.addr_validate(&address)?;
+    if exempt {
+        TAX_EXEMPT.save(deps.storage, &addr, &())?;
+    } else {
+        TAX_EXEMPT.remove(deps.storage, &addr);
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "set_tax_exempt")
+        .add_attribute("address", addr)
+        .add_attribute("exempt", exempt.to_string());
+    Ok(res)
+}
+
+pub fn query_tax_config(deps: Deps) -> StdResult<TaxConfigResponse> {
+    let tax = TAX_CONFIG.may_load(deps.storage)?;
+    Ok(TaxConfigResponse { tax })
+}
+
+pub fn query_tax_exempt(deps: Deps, address: String) -> StdResult<TaxExemptResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let exempt = TAX_EXEMPT.has(deps.storage, &address);
+    Ok(TaxExemptResponse { exempt })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{Binary, CosmosMsg, SubMsg, WasmMsg};
+    use cw20::{Cw20Coin, Cw20ReceiveMsg};
+
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    fn get_balance(deps: Deps, address: &str) -> Uint128 {
+        query_balance(deps, address.to_string()).unwrap().balance
+    }
+
+    fn setup(deps: DepsMut, tax: Option<InstantiateTaxInfo>) {
+        let instantiate_msg = InstantiateMsg {
+            name: "Tax Coin".to_string(),
+            symbol: "TAX".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "owner".to_string(),
+                amount: Uint128::new(100_000),
+            }],
+            mint: None,
+            marketing: None,
+            initial_vesting: None,
+            tax,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps, mock_env(), info, instantiate_msg).unwrap();
+    }
+
+    fn tax_info() -> InstantiateTaxInfo {
+        InstantiateTaxInfo {
+            rate_bps: 100,
+            min: Uint128::new(5),
+            max: Some(Uint128::new(500)),
+            collector: "collector".to_string(),
+            admin: "admin".to_string(),
+            exempt: vec!["exchange".to_string()],
+        }
+    }
+
+    #[test]
+    fn tax_caps() {
+        let config = TaxConfig {
+            rate_bps: 100,
+            min: Uint128::new(5),
+            max: Some(Uint128::new(500)),
+            collector: Addr::unchecked("collector"),
+            admin: Addr::unchecked("admin"),
+        };
+
+        assert_eq!(config.tax(Uint128::new(10_000)), Uint128::new(100));
+        assert_eq!(config.tax(Uint128::new(100)), Uint128::new(5));
+        assert_eq!(config.tax(Uint128::new(3)), Uint128::new(3));
+        assert_eq!(config.tax(Uint128::new(100_000)), Uint128::new(500));
+    }
+
+    #[test]
+    fn invalid_tax() {
+        let mut deps = mock_dependencies();
+        let instantiate_msg = InstantiateMsg {
+            name: "Tax Coin".to_string(),
+            symbol: "TAX".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: None,
+            marketing: None,
+            initial_vesting: None,
+            tax: Some(InstantiateTaxInfo {
+                rate_bps: 10_001,
+                ..tax_info()
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        let err = instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTaxRate {});
+
+        setup(deps.as_mut(), Some(tax_info()));
+        let msg = ExecuteMsg::UpdateTax {
+            rate_bps: 100,
+            min: Uint128::new(50),
+            max: Some(Uint128::new(10)),
+            collector: "collector".to_string(),
+            admin: "admin".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidTaxCaps {});
+    }
+
+    #[test]
+    fn transfers_are_taxed() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some(tax_info()));
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(10_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "owner"), Uint128::new(90_000));
+        assert_eq!(get_balance(deps.as_ref(), "other"), Uint128::new(9_900));
+        assert_eq!(get_balance(deps.as_ref(), "collector"), Uint128::new(100));
+
+        // the receiving contract is told the amount after tax
+        let msg = ExecuteMsg::Send {
+            contract: "contract".to_string(),
+            amount: Uint128::new(1_000),
+            msg: Binary::default(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "contract"), Uint128::new(990));
+        assert_eq!(get_balance(deps.as_ref(), "collector"), Uint128::new(110));
+        let binary_msg = Cw20ReceiveMsg {
+            sender: "owner".to_string(),
+            amount: Uint128::new(990),
+            msg: Binary::default(),
+        }
+        .into_binary()
+        .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "contract".to_string(),
+                msg: binary_msg,
+                funds: vec![],
+            }))
+        );
+    }
+
+    #[test]
+    fn exempt_transfers() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some(tax_info()));
+
+        // transfers from or to exempt addresses are not taxed
+        let msg = ExecuteMsg::Transfer {
+            recipient: "exchange".to_string(),
+            amount: Uint128::new(10_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(10_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("exchange", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "other"), Uint128::new(10_000));
+        assert_eq!(get_balance(deps.as_ref(), "collector"), Uint128::zero());
+
+        // neither is the collector spending the tax
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(1_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "collector"), Uint128::new(10));
+        let msg = ExecuteMsg::Transfer {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(10),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("collector", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "collector"), Uint128::zero());
+        assert_eq!(get_balance(deps.as_ref(), "owner"), Uint128::new(90_010));
+    }
+
+    #[test]
+    fn sends_of_only_tax_are_rejected() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some(tax_info()));
+
+        // the minimum tax takes the whole amount
+        let msg = ExecuteMsg::Send {
+            contract: "contract".to_string(),
+            amount: Uint128::new(5),
+            msg: Binary::default(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(5),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SendFrom {
+            owner: "owner".to_string(),
+            contract: "contract".to_string(),
+            amount: Uint128::new(5),
+            msg: Binary::default(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("spender", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // one more token reaches the contract
+        let msg = ExecuteMsg::Send {
+            contract: "contract".to_string(),
+            amount: Uint128::new(6),
+            msg: Binary::default(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "contract"), Uint128::new(1));
+    }
+
+    #[test]
+    fn update_tax() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), Some(tax_info()));
+
+        // only the admin can update the tax
+        let msg = ExecuteMsg::SetTaxExempt {
+            address: "owner".to_string(),
+            exempt: true,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(10_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "other"), Uint128::new(10_000));
+
+        let msg = ExecuteMsg::UpdateTax {
+            rate_bps: 1_000,
+            min: Uint128::zero(),
+            max: None,
+            collector: "treasury".to_string(),
+            admin: "new_admin".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+        let tax = query_tax_config(deps.as_ref()).unwrap().tax.unwrap();
+        assert_eq!(tax.rate_bps, 1_000);
+        assert_eq!(tax.collector, Addr::unchecked("treasury"));
+        assert_eq!(tax.admin, Addr::unchecked("new_admin"));
+        for (address, exempt) in [("exchange", true), ("owner", true), ("other", false)] {
+            let res = query_tax_exempt(deps.as_ref(), address.to_string()).unwrap();
+            assert_eq!(res.exempt, exempt);
+        }
+
+        // allowances are spent before tax
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: "spender".to_string(),
+            amount: Uint128::new(1_000),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap();
+        let msg = ExecuteMsg::TransferFrom {
+            owner: "other".to_string(),
+            recipient: "third".to_string(),
+            amount: Uint128::new(1_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("spender", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "third"), Uint128::new(900));
+        assert_eq!(get_balance(deps.as_ref(), "treasury"), Uint128::new(100));
+    }
+
+    #[test]
+    fn no_tax() {
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), None);
+        assert_eq!(query_tax_config(deps.as_ref()).unwrap().tax, None);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(10_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), "other"), Uint128::new(10_000));
+
+        // nobody can add a tax later
+        let msg = ExecuteMsg::SetTaxExempt {
+            address: "owner".to_string(),
+            exempt: true,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+}
@@ -0,0 +1,431 @@
+use cosmwasm_std::{
+    Addr, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20::BalanceResponse;
+
+use crate::contract::mint;
+use crate::error::ContractError;
+use crate::msg::{VestingCoin, VestingInfoResponse, VestingSchedule};
+use crate::state::{increase_balance, locked_balance, Vesting, BALANCES, VESTING};
+
+/// Most schedules an address can have locked at once
+const MAX_VESTING_SCHEDULES: u32 = 10;
+
+/// Locks `vesting` in the balance of `address`, dropping schedules that are fully released.
+/// Amounts with the same schedule are merged into a single entry.
+fn add_vesting(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    address: &Addr,
+    vesting: Vesting,
+) -> Result<(), ContractError> {
+    VESTING.update(storage, address, |current| -> Result<_, ContractError> {
+        let mut current = current.unwrap_or_default();
+        current.retain(|v| !v.locked(block).is_zero());
+        match current.iter_mut().find(|v| v.schedule == vesting.schedule) {
+            Some(existing) => {
+                existing.amount = existing
+                    .amount
+                    .checked_add(vesting.amount)
+                    .map_err(StdError::from)?;
+            }
+            None => {
+                if current.len() >= MAX_VESTING_SCHEDULES as usize {
+                    return Err(ContractError::TooManyVestingSchedules {
+                        max: MAX_VESTING_SCHEDULES,
+                    });
+                }
+                current.push(vesting);
+            }
+        }
+        Ok(current)
+    })?;
+    Ok(())
+}
+
+/// Creates the initial vesting balances, returns the amount they add to the total supply
+pub fn create_vesting_accounts(
+    deps: &mut DepsMut,
+    env: &Env,
+    accounts: &[VestingCoin],
+) -> Result<Uint128, ContractError> {
+    let mut total_supply = Uint128::zero();
+    for row in accounts {
+        row.schedule.validate()?;
+        let address = deps.api.addr_validate(&row.address)?;
+        increase_balance(deps.storage, &address, row.amount, &env.block)?;
+        add_vesting(
+            deps.storage,
+            &env.block,
+            &address,
+            Vesting {
+                amount: row.amount,
+                schedule: row.schedule.clone(),
+            },
+        )?;
+        total_supply += row.amount;
+    }
+
+    Ok(total_supply)
+}
+
+pub fn execute_mint_with_vesting(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+    schedule: VestingSchedule,
+) -> Result<Response, ContractError> {
+    schedule.validate()?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    let res = mint(deps.branch(), env.clone(), info, &rcpt_addr, amount)?;
+
+    let (start, cliff, end) = schedule.points();
+    add_vesting(
+        deps.storage,
+        &env.block,
+        &rcpt_addr,
+        Vesting { amount, schedule },
+    )?;
+
+    Ok(res
+        .add_attribute("vesting_start", start.to_string())
+        .add_attribute("vesting_cliff", cliff.to_string())
+        .add_attribute("vesting_end", end.to_string()))
+}
+
+pub fn query_vesting_info(deps: Deps, env: Env, address: String) -> StdResult<VestingInfoResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let vesting = VESTING
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let locked = locked_balance(deps.storage, &address, &env.block)?;
+    Ok(VestingInfoResponse { vesting, locked })
+}
+
+pub fn query_spendable_balance(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = BALANCES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    let locked = locked_balance(deps.storage, &address, &env.block)?;
+    Ok(BalanceResponse {
+        balance: balance.saturating_sub(locked),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{attr, Binary};
+    use cw20::{Cw20Coin, MinterResponse};
+
+    use crate::contract::{execute, instantiate, query_balance};
+    use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+    fn get_spendable(deps: Deps, env: &Env, address: &str) -> Uint128 {
+        query_spendable_balance(deps, env.clone(), address.to_string())
+            .unwrap()
+            .balance
+    }
+
+    #[test]
+    fn initial_vesting_by_height() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start = env.block.height;
+
+        let instantiate_msg = InstantiateMsg {
+            name: "Vesting Coin".to_string(),
+            symbol: "VEST".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: "owner".to_string(),
+                amount: Uint128::new(100),
+            }],
+            mint: None,
+            marketing: None,
+            initial_vesting: Some(vec![VestingCoin {
+                address: "owner".to_string(),
+                amount: Uint128::new(1000),
+                schedule: VestingSchedule::Height {
+                    start,
+                    cliff: start + 10,
+                    end: start + 100,
+                },
+            }]),
+            tax: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+        assert_eq!(
+            query_balance(deps.as_ref(), "owner".to_string())
+                .unwrap()
+                .balance,
+            Uint128::new(1100)
+        );
+        assert_eq!(
+            get_spendable(deps.as_ref(), &env, "owner"),
+            Uint128::new(100)
+        );
+
+        // only the liquid balance can be spent before the cliff
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(101),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LockedBalance {
+                locked: Uint128::new(1000)
+            }
+        );
+        let msg = ExecuteMsg::Send {
+            contract: "contract".to_string(),
+            amount: Uint128::new(101),
+            msg: Binary::default(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::LockedBalance { .. }));
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // past the cliff the release is linear from the start
+        env.block.height = start + 55;
+        let info = query_vesting_info(deps.as_ref(), env.clone(), "owner".to_string()).unwrap();
+        assert_eq!(info.vesting.len(), 1);
+        assert_eq!(info.locked, Uint128::new(450));
+        assert_eq!(
+            get_spendable(deps.as_ref(), &env, "owner"),
+            Uint128::new(550)
+        );
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(550),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        // everything is released at the end
+        env.block.height = start + 100;
+        assert_eq!(
+            get_spendable(deps.as_ref(), &env, "owner"),
+            Uint128::new(450)
+        );
+        let msg = ExecuteMsg::Transfer {
+            recipient: "other".to_string(),
+            amount: Uint128::new(450),
+        };
+        execute(deps.as_mut(), env, mock_info("owner", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn mint_with_vesting_by_time() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let now = env.block.time;
+
+        let instantiate_msg = InstantiateMsg {
+            name: "Vesting Coin".to_string(),
+            symbol: "VEST".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: Some(Uint128::new(500)),
+            }),
+            marketing: None,
+            initial_vesting: None,
+            tax: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+        let schedule = VestingSchedule::Time {
+            start: now,
+            cliff: now.plus_seconds(100),
+            end: now.plus_seconds(200),
+        };
+
+        // invalid schedules are rejected
+        let msg = ExecuteMsg::MintWithVesting {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(400),
+            schedule: VestingSchedule::Time {
+                start: now.plus_seconds(100),
+                cliff: now,
+                end: now.plus_seconds(200),
+            },
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("minter", &[]), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Std(StdError::GenericErr { .. })
+        ));
+
+        // only the minter can mint, within the cap
+        let msg = ExecuteMsg::MintWithVesting {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(400),
+            schedule: schedule.clone(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), env.clone(), mock_info("minter", &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "mint"),
+                attr("to", "owner"),
+                attr("amount", "400"),
+                attr("vesting_start", now.nanos().to_string()),
+                attr("vesting_cliff", now.plus_seconds(100).nanos().to_string()),
+                attr("vesting_end", now.plus_seconds(200).nanos().to_string()),
+            ]
+        );
+        let msg = ExecuteMsg::MintWithVesting {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(200),
+            schedule: schedule.clone(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info("minter", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+
+        // liquid mints are spendable right away
+        let msg = ExecuteMsg::Mint {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("minter", &[]), msg).unwrap();
+        assert_eq!(
+            get_spendable(deps.as_ref(), &env, "owner"),
+            Uint128::new(100)
+        );
+
+        // past the cliff three quarters of the schedule are released
+        env.block.time = now.plus_seconds(150);
+        assert_eq!(
+            get_spendable(deps.as_ref(), &env, "owner"),
+            Uint128::new(400)
+        );
+        let info = query_vesting_info(deps.as_ref(), env.clone(), "owner".to_string()).unwrap();
+        assert_eq!(
+            info,
+            VestingInfoResponse {
+                vesting: vec![Vesting {
+                    amount: Uint128::new(400),
+                    schedule,
+                }],
+                locked: Uint128::new(100),
+            }
+        );
+    }
+
+    #[test]
+    fn vesting_schedules_are_merged_and_capped() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let now = env.block.time;
+
+        let instantiate_msg = InstantiateMsg {
+            name: "Vesting Coin".to_string(),
+            symbol: "VEST".to_string(),
+            decimals: 6,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            initial_vesting: None,
+            tax: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+        let mint = |ends_in: u64| ExecuteMsg::MintWithVesting {
+            recipient: "owner".to_string(),
+            amount: Uint128::new(100),
+            schedule: VestingSchedule::Time {
+                start: now,
+                cliff: now,
+                end: now.plus_seconds(ends_in),
+            },
+        };
+
+        // the same schedule adds to a single entry
+        for _ in 0..3 {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("minter", &[]),
+                mint(100),
+            )
+            .unwrap();
+        }
+        let info = query_vesting_info(deps.as_ref(), env.clone(), "owner".to_string()).unwrap();
+        assert_eq!(info.vesting.len(), 1);
+        assert_eq!(info.vesting[0].amount, Uint128::new(300));
+
+        // different schedules are kept apart, up to the cap
+        for ends_in in 101..(100 + MAX_VESTING_SCHEDULES as u64) {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("minter", &[]),
+                mint(ends_in),
+            )
+            .unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minter", &[]),
+            mint(1000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TooManyVestingSchedules {
+                max: MAX_VESTING_SCHEDULES
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minter", &[]),
+            mint(100),
+        )
+        .unwrap();
+
+        // released schedules make room for new ones
+        let mut env = env;
+        env.block.time = now.plus_seconds(1000);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("minter", &[]),
+            mint(1000),
+        )
+        .unwrap();
+        let info = query_vesting_info(deps.as_ref(), env, "owner".to_string()).unwrap();
+        assert_eq!(info.vesting.len(), 1);
+    }
+}
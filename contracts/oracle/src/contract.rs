@@ -1,6 +1,8 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, entry_point, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo,
-    Response, StdError, StdResult, Timestamp, Uint128,
+    coins, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Timestamp, Uint128,
 };
 
 use crate::msg::{
@@ -9,7 +11,7 @@ use crate::msg::{
 };
 use crate::state::{OracleValue, State, ORACLE_VALUE, STATE};
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
@@ -41,7 +43,7 @@ pub fn instantiate(
     Ok(Response::default())
 }
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
     match msg {
         ExecuteMsg::GrantOracleRole { address } => try_grant_role(deps, &info, address),
@@ -167,7 +169,7 @@ fn try_set_fee(deps: DepsMut, info: &MessageInfo, amount: Uint128) -> StdResult<
         .add_attribute("amount", amount))
 }
 
-#[entry_point]
+#[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     let state = STATE.load(deps.storage)?;
     match msg {
@@ -184,7 +186,10 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             Ok(out)
         }
         QueryMsg::Fee {} => {
-            let out = to_binary(&FeeResponse { fee: state.fee })?;
+            let out = to_binary(&FeeResponse {
+                fee: state.fee,
+                denom: state.denom,
+            })?;
             Ok(out)
         }
         QueryMsg::FeesAccrued {} => {
@@ -322,7 +327,7 @@ mod tests {
             res.messages[0],
             SubMsg::new(BankMsg::Send {
                 to_address: state.owner.into_string(),
-                amount: coins(900u128.into(), state.denom),
+                amount: coins(900u128, state.denom),
             })
         );
 
@@ -1,18 +1,16 @@
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use cosmwasm_schema::{cw_serde, QueryResponses};
 
 use crate::state::OracleValue;
 
 pub type Uint128 = cosmwasm_std::Uint128;
 pub type Addr = cosmwasm_std::Addr;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct InstantiateMsg {
     pub fee: Uint128,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub enum ExecuteMsg {
     GrantOracleRole { address: Addr },
     UpdateOracleValue { value: Uint128, decimals: Uint128 },
@@ -21,37 +19,41 @@ pub enum ExecuteMsg {
     WithdrawFees { amount: Uint128 },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub enum ConsumerMsg {
     SetOracleValue { value: OracleValue },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(StatusResponse)]
     Status {},
+    #[returns(OracleRoleResponse)]
     OracleRole {},
+    #[returns(FeeResponse)]
     Fee {},
+    #[returns(FeesAccruedResponse)]
     FeesAccrued {},
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct StatusResponse {
     pub status: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct OracleRoleResponse {
     pub address: Addr,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct FeeResponse {
     pub fee: Uint128,
+    pub denom: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct FeesAccruedResponse {
     pub fees_accrued: Uint128,
 }
@@ -1,14 +1,36 @@
+use std::str::FromStr;
+
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, Event, MessageInfo, Reply,
-    Response, StdError, StdResult, SubMsg, SubMsgResponse, Timestamp, Uint128, WasmMsg,
+    coins, entry_point, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgResponse, Timestamp, Uint128, WasmMsg,
 };
+use cw_storage_plus::Bound;
 
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, OracleContractResponse, OracleMsg, OracleValueResponse, QueryMsg,
+    AllowedCallersResponse, Callback, CallbackMsg, ConfigResponse, ExecuteMsg, FeeBudgetResponse,
+    InstantiateMsg, LastErrorResponse, OracleContractResponse, OracleFeeResponse, OracleMsg,
+    OracleQueryMsg, OracleValueResponse, QueryMsg, RefreshPolicyResponse, RejectedUpdatesResponse,
+    StatsResponse, UpdateConfigMsg, ValueAtResponse, ValueHistoryResponse,
+};
+use crate::state::{
+    CacheStats, OracleError, OracleValue, QueryMode, RefreshPolicy, RejectedUpdate, State,
+    ALLOWED_CALLERS, CACHE_STATS, LAST_ERROR, LAST_REFRESH, LAST_UPDATED, ORACLE_VALUE,
+    PENDING_CALLBACK, REJECTED_UPDATES, REJECTED_UPDATE_COUNT, STATE, VALUE_HISTORY,
+    VALUE_HISTORY_SIZE,
 };
-use crate::state::{OracleValue, State, ORACLE_VALUE, STATE};
 
 const ORACLE_REPLY_ID: u64 = 1u64;
+const CALLBACK_REPLY_ID: u64 = 2u64;
+
+// number of rejected updates kept in the log
+const MAX_REJECTED_UPDATES: u64 = 100;
+// number of stored values kept in the history
+const MAX_VALUE_HISTORY: u64 = 100;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[entry_point]
 pub fn instantiate(
@@ -24,11 +46,22 @@ pub fn instantiate(
     };
 
     let state = State {
-        oracle_contract_address: msg.oracle_contract_address,
+        oracle_contract_address: deps.api.addr_validate(&msg.oracle_contract_address)?,
         owner: info.sender,
+        max_age: msg.max_age,
+        required_decimals: msg.required_decimals,
+        refresh_policy: msg.refresh_policy.unwrap_or(RefreshPolicy::Allowlist {}),
+        cache_ttl: msg.cache_ttl,
+        min_value: msg.min_value,
+        max_value: msg.max_value,
+        catch_oracle_errors: msg.catch_oracle_errors.unwrap_or(false),
+        query_mode: msg.query_mode.unwrap_or(QueryMode::Reply {}),
     };
     STATE.save(deps.storage, &state)?;
     ORACLE_VALUE.save(deps.storage, &oracle_value)?;
+    CACHE_STATS.save(deps.storage, &CacheStats::default())?;
+    REJECTED_UPDATE_COUNT.save(deps.storage, &0u64)?;
+    VALUE_HISTORY_SIZE.save(deps.storage, &0u64)?;
 
     Ok(Response::default())
 }
@@ -37,35 +70,594 @@ pub fn instantiate(
 #[entry_point]
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
     match msg {
-        ExecuteMsg::QueryOracleValue {} => try_query_oracle_value(deps, &env, &info),
+        ExecuteMsg::QueryOracleValue { callback } => {
+            try_query_oracle_value(deps, &env, &info, callback)
+        }
+        ExecuteMsg::SetOracleValue { value } => try_set_oracle_value(deps, &env, &info, value),
+        ExecuteMsg::UpdateConfig(config) => try_update_config(deps, &info, config),
+        ExecuteMsg::TransferOwnership { owner } => try_transfer_ownership(deps, &info, owner),
+        ExecuteMsg::FundFeeBudget {} => try_fund_fee_budget(deps, &info),
+        ExecuteMsg::WithdrawFeeBudget { amount } => try_withdraw_fee_budget(deps, &info, amount),
+        ExecuteMsg::SetRefreshPolicy { refresh_policy } => {
+            try_set_refresh_policy(deps, &info, refresh_policy)
+        }
+        ExecuteMsg::AddAllowedCaller { address } => try_add_allowed_caller(deps, &info, address),
+        ExecuteMsg::RemoveAllowedCaller { address } => {
+            try_remove_allowed_caller(deps, &info, address)
+        }
     }
 }
 
-// Get the oracle value stored on the oracle contract
-fn try_query_oracle_value(deps: DepsMut, env: &Env, info: &MessageInfo) -> StdResult<Response> {
+// Get the oracle value stored on the oracle contract, paying the fee from the fee budget,
+// unless the cached value is still fresh or the oracle is read with a smart query.
+// The value is forwarded to the optional callback.
+fn try_query_oracle_value(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    callback: Option<Callback>,
+) -> StdResult<Response> {
     let state = STATE.load(deps.storage)?;
 
-    let msg = to_binary(&OracleMsg::QueryOracleValue {
-        address: env.contract.address.clone(),
+    if !info.funds.is_empty() {
+        return Err(StdError::generic_err(
+            "Oracle fees are paid from the fee budget, no funds should be sent",
+        ));
+    }
+
+    if let Some(callback) = callback.as_ref() {
+        deps.api.addr_validate(&callback.contract_addr)?;
+    }
+
+    let cache_hit = is_cache_fresh(deps.storage, env, &state)?;
+    CACHE_STATS.update(deps.storage, |mut stats| -> Result<_, StdError> {
+        if cache_hit {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        Ok(stats)
     })?;
 
+    if cache_hit {
+        let oracle_value = ORACLE_VALUE.load(deps.storage)?;
+        let mut res = Response::new();
+        if let Some(callback) = callback {
+            res = res.add_submessage(callback_submessage(callback, &oracle_value)?);
+        }
+        return Ok(res
+            .add_attribute("action", "query_oracle_value")
+            .add_attribute("cache", "hit")
+            .add_attribute("oracle-value", oracle_value.value)
+            .add_attribute("decimals", oracle_value.decimals)
+            .add_attribute("timestamp", oracle_value.timestamp.seconds().to_string()));
+    }
+
+    if state.query_mode == (QueryMode::Smart {}) {
+        return refresh_with_smart_query(deps, env, &state, callback);
+    }
+
+    check_refresh_allowed(deps.storage, env, &state, &info.sender)?;
+
+    let fee: OracleFeeResponse = deps.querier.query_wasm_smart(
+        state.oracle_contract_address.to_string(),
+        &OracleQueryMsg::Fee {},
+    )?;
+
+    let mut funds = vec![];
+    if !fee.fee.is_zero() {
+        let budget = deps
+            .querier
+            .query_balance(env.contract.address.clone(), fee.denom.clone())?;
+        if budget.amount < fee.fee {
+            return Err(StdError::generic_err("Insufficient fee budget"));
+        }
+        funds = coins(fee.fee.u128(), fee.denom);
+    }
+
+    let msg = to_binary(&OracleMsg::QueryOracleValue {})?;
+
     let message: CosmosMsg = WasmMsg::Execute {
         contract_addr: state.oracle_contract_address.to_string(),
         msg,
-        funds: info.funds.clone(),
+        funds,
     }
     .into();
 
-    let submessage = SubMsg::reply_on_success(message, ORACLE_REPLY_ID);
+    let submessage = if state.catch_oracle_errors {
+        SubMsg::reply_always(message, ORACLE_REPLY_ID)
+    } else {
+        SubMsg::reply_on_success(message, ORACLE_REPLY_ID)
+    };
+
+    match callback {
+        Some(callback) => PENDING_CALLBACK.save(deps.storage, &callback)?,
+        None => PENDING_CALLBACK.remove(deps.storage),
+    }
 
     Ok(Response::new()
         .add_submessage(submessage)
-        .add_attribute("action", "query_oracle_value"))
+        .add_attribute("action", "query_oracle_value")
+        .add_attribute("cache", "miss")
+        .add_attribute("fee", fee.fee))
+}
+
+// Read the value from the oracle within the same transaction, no fee or reply round trip
+fn refresh_with_smart_query(
+    deps: DepsMut,
+    env: &Env,
+    state: &State,
+    callback: Option<Callback>,
+) -> StdResult<Response> {
+    let mut res = Response::new()
+        .add_attribute("action", "query_oracle_value")
+        .add_attribute("cache", "miss");
+
+    let oracle_value: OracleValue = match deps.querier.query_wasm_smart(
+        state.oracle_contract_address.to_string(),
+        &OracleQueryMsg::OracleValue {},
+    ) {
+        Ok(oracle_value) => oracle_value,
+        Err(error) if state.catch_oracle_errors => {
+            LAST_ERROR.save(
+                deps.storage,
+                &OracleError {
+                    error: error.to_string(),
+                    timestamp: env.block.time,
+                },
+            )?;
+            return Ok(res
+                .add_attribute("status", "oracle_error")
+                .add_attribute("error", error.to_string()));
+        }
+        Err(error) => return Err(error),
+    };
+
+    // the oracle may not have been updated since the last read
+    let stored = if oracle_value == ORACLE_VALUE.load(deps.storage)? {
+        res = res.add_attribute("status", "unchanged");
+        true
+    } else {
+        let (stored, event) = store_oracle_value(deps.storage, env, &oracle_value)?;
+        res = res.add_event(event);
+        stored
+    };
+
+    if let Some(callback) = callback {
+        if stored {
+            res = res.add_submessage(callback_submessage(callback, &oracle_value)?);
+        } else {
+            res = res.add_attribute("callback", "skipped");
+        }
+    }
+
+    Ok(res)
+}
+
+// Current oracle value for use by other handlers: read from the oracle in smart query mode,
+// where it has to pass the configured checks, otherwise the stored value
+pub fn load_oracle_value(deps: Deps, env: &Env) -> StdResult<OracleValue> {
+    let state = STATE.load(deps.storage)?;
+
+    if state.query_mode != (QueryMode::Smart {}) {
+        return ORACLE_VALUE.load(deps.storage);
+    }
+
+    let oracle_value: OracleValue = deps.querier.query_wasm_smart(
+        state.oracle_contract_address.to_string(),
+        &OracleQueryMsg::OracleValue {},
+    )?;
+    if let Some(reason) = check_value_config(&state, env, &oracle_value) {
+        return Err(StdError::generic_err(reason));
+    }
+
+    Ok(oracle_value)
+}
+
+// Forward an oracle value to a callback contract, failures are reported in the reply
+fn callback_submessage(callback: Callback, oracle_value: &OracleValue) -> StdResult<SubMsg> {
+    let msg = to_binary(&CallbackMsg::ReceiveOracleValue {
+        oracle_value: oracle_value.clone(),
+        msg: callback.msg,
+    })?;
+
+    let message: CosmosMsg = WasmMsg::Execute {
+        contract_addr: callback.contract_addr,
+        msg,
+        funds: vec![],
+    }
+    .into();
+
+    Ok(SubMsg::reply_on_error(message, CALLBACK_REPLY_ID))
+}
+
+// The cached value is fresh if it was refreshed less than `cache_ttl` seconds ago
+fn is_cache_fresh(storage: &dyn Storage, env: &Env, state: &State) -> StdResult<bool> {
+    let cache_ttl = match state.cache_ttl {
+        Some(cache_ttl) => cache_ttl,
+        None => return Ok(false),
+    };
+
+    match LAST_UPDATED.may_load(storage)? {
+        Some(last_updated) => {
+            let expires = saturating_plus_seconds(last_updated, cache_ttl);
+            Ok(env.block.time < expires)
+        }
+        None => Ok(false),
+    }
+}
+
+// Check the caller against the refresh policy, the owner can always refresh
+fn check_refresh_allowed(
+    storage: &mut dyn Storage,
+    env: &Env,
+    state: &State,
+    caller: &Addr,
+) -> StdResult<()> {
+    if *caller == state.owner {
+        return Ok(());
+    }
+
+    match state.refresh_policy {
+        RefreshPolicy::Allowlist {} => {
+            if !ALLOWED_CALLERS.has(storage, caller) {
+                return Err(StdError::generic_err(
+                    "Not authorized to refresh the oracle value",
+                ));
+            }
+        }
+        RefreshPolicy::RateLimit { interval } => {
+            if let Some(last_refresh) = LAST_REFRESH.may_load(storage, caller)? {
+                if env.block.time < saturating_plus_seconds(last_refresh, interval) {
+                    return Err(StdError::generic_err("Refresh rate limit exceeded"));
+                }
+            }
+            LAST_REFRESH.save(storage, caller, &env.block.time)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Accept an oracle value pushed by the oracle contract
+fn try_set_oracle_value(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    oracle_value: OracleValue,
+) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.oracle_contract_address {
+        return Err(StdError::generic_err(
+            "Only the oracle contract can set the oracle value",
+        ));
+    }
+
+    let (_, event) = store_oracle_value(deps.storage, env, &oracle_value)?;
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("action", "set_oracle_value"))
+}
+
+// Store a received oracle value if it passes the checks against the stored value and
+// config, otherwise record it in the rejected updates log. Returns whether it was stored.
+fn store_oracle_value(
+    storage: &mut dyn Storage,
+    env: &Env,
+    oracle_value: &OracleValue,
+) -> StdResult<(bool, Event)> {
+    if let Some(reason) = check_oracle_value(storage, env, oracle_value)? {
+        let id = REJECTED_UPDATE_COUNT.load(storage)?;
+        REJECTED_UPDATES.save(
+            storage,
+            id,
+            &RejectedUpdate {
+                id,
+                oracle_value: oracle_value.clone(),
+                reason: reason.clone(),
+                received_at: env.block.time,
+            },
+        )?;
+        if id >= MAX_REJECTED_UPDATES {
+            REJECTED_UPDATES.remove(storage, id - MAX_REJECTED_UPDATES);
+        }
+        REJECTED_UPDATE_COUNT.save(storage, &(id + 1))?;
+
+        let event = oracle_value_event("oracle-value-rejected", oracle_value)
+            .add_attribute("reason", reason);
+        return Ok((false, event));
+    }
+
+    ORACLE_VALUE.save(storage, oracle_value)?;
+    LAST_UPDATED.save(storage, &env.block.time)?;
+    add_to_value_history(storage, oracle_value)?;
+
+    Ok((
+        true,
+        oracle_value_event("oracle-value-update", oracle_value),
+    ))
+}
+
+// Add a stored value to the history, dropping the oldest value once the history is full
+fn add_to_value_history(storage: &mut dyn Storage, oracle_value: &OracleValue) -> StdResult<()> {
+    VALUE_HISTORY.save(storage, oracle_value.timestamp.seconds(), oracle_value)?;
+
+    let size = VALUE_HISTORY_SIZE.load(storage)? + 1;
+    if size > MAX_VALUE_HISTORY {
+        let oldest = VALUE_HISTORY
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?;
+        if let Some(oldest) = oldest {
+            VALUE_HISTORY.remove(storage, oldest);
+        }
+        return Ok(());
+    }
+
+    VALUE_HISTORY_SIZE.save(storage, &size)
+}
+
+// Returns the reason for rejecting a received oracle value, if any
+fn check_oracle_value(
+    storage: &dyn Storage,
+    env: &Env,
+    oracle_value: &OracleValue,
+) -> StdResult<Option<String>> {
+    let state = STATE.load(storage)?;
+    let stored_value = ORACLE_VALUE.load(storage)?;
+
+    if oracle_value.timestamp <= stored_value.timestamp {
+        return Ok(Some(String::from(
+            "Oracle value is not newer than the stored value",
+        )));
+    }
+
+    Ok(check_value_config(&state, env, oracle_value))
+}
+
+// `time` plus `seconds`, saturating at the largest timestamp rather than overflowing
+fn saturating_plus_seconds(time: Timestamp, seconds: u64) -> Timestamp {
+    Timestamp::from_nanos(
+        time.nanos()
+            .saturating_add(seconds.saturating_mul(1_000_000_000)),
+    )
+}
+
+// Returns the reason an oracle value does not match the configured age, decimals and bounds
+fn check_value_config(state: &State, env: &Env, oracle_value: &OracleValue) -> Option<String> {
+    if let Some(max_age) = state.max_age {
+        if saturating_plus_seconds(oracle_value.timestamp, max_age) < env.block.time {
+            return Some(String::from("Oracle value is too old"));
+        }
+    }
+
+    if let Some(required_decimals) = state.required_decimals {
+        if oracle_value.decimals != required_decimals {
+            return Some(format!(
+                "Oracle value has {} decimals, expected {}",
+                oracle_value.decimals, required_decimals
+            ));
+        }
+    }
+
+    if let Some(min_value) = state.min_value {
+        if oracle_value.value < min_value {
+            return Some(format!(
+                "Oracle value is below the minimum of {}",
+                min_value
+            ));
+        }
+    }
+
+    if let Some(max_value) = state.max_value {
+        if oracle_value.value > max_value {
+            return Some(format!(
+                "Oracle value is above the maximum of {}",
+                max_value
+            ));
+        }
+    }
+
+    None
+}
+
+fn oracle_value_event(ty: &str, oracle_value: &OracleValue) -> Event {
+    Event::new(ty)
+        .add_attribute("oracle-value", oracle_value.value)
+        .add_attribute("decimals", oracle_value.decimals)
+        .add_attribute("timestamp", oracle_value.timestamp.seconds().to_string())
+}
+
+fn try_update_config(
+    deps: DepsMut,
+    info: &MessageInfo,
+    config: UpdateConfigMsg,
+) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let UpdateConfigMsg {
+        oracle_contract_address,
+        max_age,
+        required_decimals,
+        cache_ttl,
+        min_value,
+        max_value,
+        catch_oracle_errors,
+        query_mode,
+    } = config;
+    let oracle_contract_address = oracle_contract_address
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+
+    let state = STATE.update(deps.storage, |mut state: State| -> Result<_, StdError> {
+        if let Some(address) = oracle_contract_address {
+            state.oracle_contract_address = address;
+        }
+        if let Some(max_age) = max_age {
+            state.max_age = max_age;
+        }
+        if let Some(required_decimals) = required_decimals {
+            state.required_decimals = required_decimals;
+        }
+        if let Some(cache_ttl) = cache_ttl {
+            state.cache_ttl = cache_ttl;
+        }
+        if let Some(min_value) = min_value {
+            state.min_value = min_value;
+        }
+        if let Some(max_value) = max_value {
+            state.max_value = max_value;
+        }
+        if let Some(catch_oracle_errors) = catch_oracle_errors {
+            state.catch_oracle_errors = catch_oracle_errors;
+        }
+        if let Some(query_mode) = query_mode {
+            state.query_mode = query_mode;
+        }
+        Ok(state)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("oracle_contract_address", state.oracle_contract_address))
+}
+
+fn try_transfer_ownership(deps: DepsMut, info: &MessageInfo, owner: String) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let owner = deps.api.addr_validate(&owner)?;
+
+    STATE.update(deps.storage, |mut state: State| -> Result<_, StdError> {
+        state.owner = owner.clone();
+        Ok(state)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("owner", owner))
+}
+
+fn try_fund_fee_budget(deps: DepsMut, info: &MessageInfo) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    if info.funds.is_empty() {
+        return Err(StdError::generic_err("No funds sent"));
+    }
+
+    let mut res = Response::new().add_attribute("action", "fund_fee_budget");
+    for coin in info.funds.iter() {
+        res = res.add_attribute("amount", coin.to_string());
+    }
+    Ok(res)
+}
+
+fn try_withdraw_fee_budget(deps: DepsMut, info: &MessageInfo, amount: Coin) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let message: BankMsg = BankMsg::Send {
+        to_address: state.owner.into_string(),
+        amount: vec![amount.clone()],
+    };
+
+    Ok(Response::new()
+        .add_message(message)
+        .add_attribute("action", "withdraw_fee_budget")
+        .add_attribute("amount", amount.to_string()))
+}
+
+fn try_set_refresh_policy(
+    deps: DepsMut,
+    info: &MessageInfo,
+    refresh_policy: RefreshPolicy,
+) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    STATE.update(deps.storage, |mut state: State| -> Result<_, StdError> {
+        state.refresh_policy = refresh_policy;
+        Ok(state)
+    })?;
+
+    Ok(Response::new().add_attribute("action", "set_refresh_policy"))
+}
+
+fn try_add_allowed_caller(
+    deps: DepsMut,
+    info: &MessageInfo,
+    address: String,
+) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    ALLOWED_CALLERS.save(deps.storage, &address, &())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_allowed_caller")
+        .add_attribute("address", address))
+}
+
+fn try_remove_allowed_caller(
+    deps: DepsMut,
+    info: &MessageInfo,
+    address: String,
+) -> StdResult<Response> {
+    let state = STATE.load(deps.storage)?;
+
+    if info.sender != state.owner {
+        return Err(StdError::generic_err("Unauthorized"));
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    ALLOWED_CALLERS.remove(deps.storage, &address);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_allowed_caller")
+        .add_attribute("address", address))
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::Config {} => {
+            let state = STATE.load(deps.storage)?;
+            let out = to_binary(&ConfigResponse {
+                owner: state.owner,
+                oracle_contract_address: state.oracle_contract_address,
+                max_age: state.max_age,
+                required_decimals: state.required_decimals,
+                cache_ttl: state.cache_ttl,
+                min_value: state.min_value,
+                max_value: state.max_value,
+                catch_oracle_errors: state.catch_oracle_errors,
+                query_mode: state.query_mode,
+            })?;
+            Ok(out)
+        }
         QueryMsg::OracleContract {} => {
             let state = STATE.load(deps.storage)?;
             let out = to_binary(&OracleContractResponse {
@@ -74,17 +666,116 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             Ok(out)
         }
         QueryMsg::OracleValue {} => {
-            let oracle_value = ORACLE_VALUE.load(deps.storage)?;
+            let oracle_value = load_oracle_value(deps, &env)?;
             let out = to_binary(&OracleValueResponse { oracle_value })?;
             Ok(out)
         }
+        QueryMsg::FeeBudget {} => {
+            let budget = deps.querier.query_all_balances(env.contract.address)?;
+            let out = to_binary(&FeeBudgetResponse { budget })?;
+            Ok(out)
+        }
+        QueryMsg::RefreshPolicy {} => {
+            let state = STATE.load(deps.storage)?;
+            let out = to_binary(&RefreshPolicyResponse {
+                refresh_policy: state.refresh_policy,
+            })?;
+            Ok(out)
+        }
+        QueryMsg::AllowedCallers { start_after, limit } => {
+            to_binary(&query_allowed_callers(deps, start_after, limit)?)
+        }
+        QueryMsg::Stats {} => {
+            let cache = CACHE_STATS.load(deps.storage)?;
+            let out = to_binary(&StatsResponse { cache })?;
+            Ok(out)
+        }
+        QueryMsg::RejectedUpdates { start_after, limit } => {
+            to_binary(&query_rejected_updates(deps, start_after, limit)?)
+        }
+        QueryMsg::ValueHistory { start_after, limit } => {
+            to_binary(&query_value_history(deps, start_after, limit)?)
+        }
+        QueryMsg::ValueAt { timestamp } => to_binary(&query_value_at(deps, timestamp)?),
+        QueryMsg::LastError {} => {
+            let last_error = LAST_ERROR.may_load(deps.storage)?;
+            let out = to_binary(&LastErrorResponse { last_error })?;
+            Ok(out)
+        }
     }
 }
 
+fn query_value_history(
+    deps: Deps,
+    start_after: Option<Timestamp>,
+    limit: Option<u32>,
+) -> StdResult<ValueHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|timestamp| Bound::exclusive(timestamp.seconds()));
+
+    let values = VALUE_HISTORY
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, oracle_value)| oracle_value))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ValueHistoryResponse { values })
+}
+
+// Latest stored value at or before the given time
+fn query_value_at(deps: Deps, timestamp: Timestamp) -> StdResult<ValueAtResponse> {
+    let end = Some(Bound::inclusive(timestamp.seconds()));
+
+    let oracle_value = VALUE_HISTORY
+        .range(deps.storage, None, end, Order::Descending)
+        .next()
+        .transpose()?
+        .map(|(_, oracle_value)| oracle_value);
+
+    Ok(ValueAtResponse { oracle_value })
+}
+
+fn query_rejected_updates(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RejectedUpdatesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let updates = REJECTED_UPDATES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, update)| update))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RejectedUpdatesResponse { updates })
+}
+
+fn query_allowed_callers(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllowedCallersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let callers = ALLOWED_CALLERS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllowedCallersResponse { callers })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> StdResult<Response> {
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> StdResult<Response> {
     match reply.id {
-        ORACLE_REPLY_ID => handle_oracle_reply(deps, reply),
+        ORACLE_REPLY_ID => handle_oracle_reply(deps, &env, reply),
+        CALLBACK_REPLY_ID => handle_callback_reply(reply),
         id => Err(StdError::generic_err(format!("Unknown reply id: {}", id))),
     }
 }
@@ -97,8 +788,25 @@ pub fn event_contains_attr(event: &Event, key: &str) -> bool {
     event.attributes.iter().any(|attr| attr.key == key)
 }
 
-fn handle_oracle_reply(deps: DepsMut, msg: Reply) -> StdResult<Response> {
-    let sub_response = unwrap_reply(msg)?;
+// Reads attribute `key` of the oracle reply event, malformed values are rejected rather than trusted
+fn oracle_reply_attr<T: FromStr>(event: &Event, key: &str) -> StdResult<T> {
+    let value = &event
+        .attributes
+        .iter()
+        .find(|attr| attr.key == key)
+        .ok_or_else(|| StdError::generic_err(format!("cannot find `{}` attribute", key)))?
+        .value;
+    value
+        .parse::<T>()
+        .map_err(|_| StdError::generic_err(format!("invalid `{}` attribute: {}", key, value)))
+}
+
+fn handle_oracle_reply(deps: DepsMut, env: &Env, msg: Reply) -> StdResult<Response> {
+    let sub_response = match msg.result.into_result() {
+        Ok(sub_response) => sub_response,
+        // only received when oracle errors are caught
+        Err(error) => return handle_oracle_error(deps, env, error),
+    };
 
     // SubMsgResponse
     // https://docs.rs/cosmwasm-std/latest/cosmwasm_std/struct.SubMsgResponse.html
@@ -109,69 +817,892 @@ fn handle_oracle_reply(deps: DepsMut, msg: Reply) -> StdResult<Response> {
         .find(|event| event_contains_attr(event, "oracle-value"))
         .ok_or_else(|| StdError::generic_err("cannot find oracle reply event"))?;
 
-    let value = event
-        .attributes
-        .iter()
-        .find(|attr| attr.key == "oracle-value")
-        .cloned()
-        .ok_or_else(|| StdError::generic_err("cannot find `oracle-value` attribute"))?
-        .value;
+    let value: u128 = oracle_reply_attr(event, "oracle-value")?;
+    let decimals: u128 = oracle_reply_attr(event, "decimals")?;
+    // seconds beyond the range of a timestamp are as malformed as any other value
+    let seconds: u64 = oracle_reply_attr(event, "timestamp")?;
+    let nanos = seconds.checked_mul(1_000_000_000).ok_or_else(|| {
+        StdError::generic_err(format!("invalid `timestamp` attribute: {}", seconds))
+    })?;
 
-    let decimals = event
-        .attributes
-        .iter()
-        .find(|attr| attr.key == "decimals")
-        .cloned()
-        .ok_or_else(|| StdError::generic_err("cannot find `decimals` attribute"))?
-        .value;
+    let oracle_value = OracleValue {
+        value: Uint128::from(value),
+        decimals: Uint128::from(decimals),
+        timestamp: Timestamp::from_nanos(nanos),
+    };
 
-    let timestamp = event
-        .attributes
-        .iter()
-        .find(|attr| attr.key == "timestamp")
-        .cloned()
-        .ok_or_else(|| StdError::generic_err("cannot find `timestamp` attribute"))?
-        .value;
+    let (stored, event) = store_oracle_value(deps.storage, env, &oracle_value)?;
+    let mut res = Response::new().add_event(event);
+
+    if let Some(callback) = PENDING_CALLBACK.may_load(deps.storage)? {
+        PENDING_CALLBACK.remove(deps.storage);
+        if stored {
+            res = res.add_submessage(callback_submessage(callback, &oracle_value)?);
+        } else {
+            res = res.add_attribute("callback", "skipped");
+        }
+    }
+
+    Ok(res)
+}
 
-    ORACLE_VALUE.update(
+// The failed oracle call is reverted, so the fee sent with it stays in the fee budget
+fn handle_oracle_error(deps: DepsMut, env: &Env, error: String) -> StdResult<Response> {
+    LAST_ERROR.save(
         deps.storage,
-        |mut oracle_value: OracleValue| -> Result<_, StdError> {
-            oracle_value.value = Uint128::from(value.parse::<u128>().unwrap());
-            oracle_value.decimals = Uint128::from(decimals.parse::<u128>().unwrap());
-            oracle_value.timestamp = Timestamp::from_seconds(timestamp.parse::<u64>().unwrap());
-            Ok(oracle_value)
+        &OracleError {
+            error: error.clone(),
+            timestamp: env.block.time,
         },
     )?;
+    PENDING_CALLBACK.remove(deps.storage);
 
-    Ok(Response::new())
+    Ok(Response::new()
+        .add_attribute("status", "oracle_error")
+        .add_attribute("error", error))
+}
+
+// Only called when the callback failed, which is reported without reverting the stored value
+fn handle_callback_reply(msg: Reply) -> StdResult<Response> {
+    let mut res = Response::new();
+    if let Err(err) = msg.result.into_result() {
+        res = res.add_event(Event::new("oracle-callback-failed").add_attribute("error", err));
+    }
+    Ok(res)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, Addr};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        coins, from_binary, from_slice, ContractResult, OwnedDeps, ReplyOn, SubMsgResult,
+        SystemError, SystemResult, WasmQuery,
+    };
 
     use crate::msg::InstantiateMsg;
 
     pub const TEST_ORACLE_ADDRESS: &str = "fetch1egrrjxyt0506aq2r6jh7nldd6hw73a55pg0smj";
 
-    fn init_msg(oracle_contract_address: Addr) -> InstantiateMsg {
+    fn init_msg(oracle_contract_address: &str) -> InstantiateMsg {
         InstantiateMsg {
-            oracle_contract_address,
+            oracle_contract_address: oracle_contract_address.to_string(),
+            max_age: None,
+            required_decimals: None,
+            refresh_policy: None,
+            cache_ttl: None,
+            min_value: None,
+            max_value: None,
+            catch_oracle_errors: None,
+            query_mode: None,
         }
     }
 
+    fn mock_oracle_fee(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, fee: u128) {
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == TEST_ORACLE_ADDRESS => {
+                let response = OracleFeeResponse {
+                    fee: Uint128::from(fee),
+                    denom: String::from("atestfet"),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: String::from("wasm"),
+            }),
+        });
+    }
+
     #[test]
     fn test_proper_initialization() {
         let mut deps = mock_dependencies();
         let env = mock_env();
         let info = mock_info("creator", &coins(1000, "earth"));
 
-        let addr = Addr::unchecked(TEST_ORACLE_ADDRESS);
-        let msg = init_msg(addr);
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
         // we can just call .unwrap() to assert this was a success
         let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(0, res.messages.len());
     }
+
+    #[test]
+    fn test_set_oracle_value() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        let oracle_value = OracleValue {
+            value: Uint128::from(100000u128),
+            decimals: Uint128::from(5u128),
+            timestamp: Timestamp::from_seconds(1000),
+        };
+
+        // only the oracle contract can push values
+        let msg = ExecuteMsg::SetOracleValue {
+            value: oracle_value.clone(),
+        };
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        let info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.events.len());
+        assert_eq!("oracle-value-update", res.events[0].ty);
+
+        let stored_value = ORACLE_VALUE.load(&deps.storage).unwrap();
+        assert_eq!(oracle_value, stored_value);
+
+        // values that are not newer than the stored one are rejected
+        for timestamp in [500, 1000] {
+            let msg = ExecuteMsg::SetOracleValue {
+                value: OracleValue {
+                    value: Uint128::from(200000u128),
+                    decimals: Uint128::from(5u128),
+                    timestamp: Timestamp::from_seconds(timestamp),
+                },
+            };
+            let info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            assert_eq!("oracle-value-rejected", res.events[0].ty);
+        }
+
+        let stored_value = ORACLE_VALUE.load(&deps.storage).unwrap();
+        assert_eq!(oracle_value, stored_value);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RejectedUpdates {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let rejected: RejectedUpdatesResponse = from_binary(&res).unwrap();
+        assert_eq!(2, rejected.updates.len());
+        assert_eq!(
+            Timestamp::from_seconds(500),
+            rejected.updates[0].oracle_value.timestamp
+        );
+        assert_eq!(
+            "Oracle value is not newer than the stored value",
+            rejected.updates[0].reason
+        );
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            oracle_contract_address: Some(String::from("new_oracle")),
+            max_age: Some(Some(60)),
+            required_decimals: Some(Some(Uint128::from(5u128))),
+            cache_ttl: None,
+            min_value: Some(Some(Uint128::from(1u128))),
+            max_value: Some(Some(Uint128::from(1000000u128))),
+            catch_oracle_errors: None,
+            query_mode: None,
+        });
+
+        // only the owner can update the config
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!("creator", config.owner);
+        assert_eq!("new_oracle", config.oracle_contract_address);
+        assert_eq!(Some(60), config.max_age);
+        assert_eq!(Some(Uint128::from(5u128)), config.required_decimals);
+        assert_eq!(Some(Uint128::from(1u128)), config.min_value);
+        assert_eq!(Some(Uint128::from(1000000u128)), config.max_value);
+
+        let env = mock_env();
+        let rejected_values = [
+            // wrong decimals
+            (1000u128, 2u128, env.block.time),
+            // older than the maximum age
+            (100000u128, 5u128, env.block.time.minus_seconds(61)),
+            // below the minimum value
+            (0u128, 5u128, env.block.time),
+            // above the maximum value
+            (1000001u128, 5u128, env.block.time),
+        ];
+        for (value, decimals, timestamp) in rejected_values {
+            let msg = ExecuteMsg::SetOracleValue {
+                value: OracleValue {
+                    value: Uint128::from(value),
+                    decimals: Uint128::from(decimals),
+                    timestamp,
+                },
+            };
+            let info = mock_info("new_oracle", &[]);
+            let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+            assert_eq!("oracle-value-rejected", res.events[0].ty);
+        }
+
+        let msg = ExecuteMsg::SetOracleValue {
+            value: OracleValue {
+                value: Uint128::from(100000u128),
+                decimals: Uint128::from(5u128),
+                timestamp: env.block.time.minus_seconds(60),
+            },
+        };
+        let info = mock_info("new_oracle", &[]);
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!("oracle-value-update", res.events[0].ty);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RejectedUpdates {
+                start_after: Some(1),
+                limit: Some(10),
+            },
+        )
+        .unwrap();
+        let rejected: RejectedUpdatesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![2, 3],
+            rejected
+                .updates
+                .iter()
+                .map(|update| update.id)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_max_age_does_not_overflow() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.max_age = Some(u64::MAX);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // a maximum age past the largest timestamp accepts any value
+        let msg = ExecuteMsg::SetOracleValue {
+            value: OracleValue {
+                value: Uint128::from(100000u128),
+                decimals: Uint128::from(5u128),
+                timestamp: mock_env().block.time,
+            },
+        };
+        let info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!("oracle-value-update", res.events[0].ty);
+
+        // as does a regular maximum age for a value at the largest timestamp
+        let msg = ExecuteMsg::UpdateConfig(from_slice(br#"{"max_age":60}"#).unwrap());
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::SetOracleValue {
+            value: OracleValue {
+                value: Uint128::from(100000u128),
+                decimals: Uint128::from(5u128),
+                timestamp: Timestamp::from_nanos(u64::MAX),
+            },
+        };
+        let info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!("oracle-value-update", res.events[0].ty);
+    }
+
+    #[test]
+    fn test_update_config_keeps_unset_fields() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.max_age = Some(60);
+        msg.min_value = Some(Uint128::from(1u128));
+        msg.catch_oracle_errors = Some(true);
+        msg.query_mode = Some(QueryMode::Smart {});
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the maximum age is removed, with null
+        let msg: ExecuteMsg = from_slice(br#"{"update_config":{"max_age":null}}"#).unwrap();
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(TEST_ORACLE_ADDRESS, config.oracle_contract_address);
+        assert_eq!(None, config.max_age);
+        assert_eq!(Some(Uint128::from(1u128)), config.min_value);
+        assert!(config.catch_oracle_errors);
+        assert_eq!(QueryMode::Smart {}, config.query_mode);
+
+        // fields left out are not serialized, so they also stay unchanged
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            oracle_contract_address: None,
+            max_age: None,
+            required_decimals: None,
+            cache_ttl: Some(Some(30)),
+            min_value: None,
+            max_value: None,
+            catch_oracle_errors: None,
+            query_mode: None,
+        });
+        let msg: ExecuteMsg = from_binary(&to_binary(&msg).unwrap()).unwrap();
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(30), config.cache_ttl);
+        assert_eq!(Some(Uint128::from(1u128)), config.min_value);
+        assert!(config.catch_oracle_errors);
+        assert_eq!(QueryMode::Smart {}, config.query_mode);
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        let msg = ExecuteMsg::TransferOwnership {
+            owner: String::from("new_owner"),
+        };
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let state = STATE.load(&deps.storage).unwrap();
+        assert_eq!("new_owner", state.owner);
+
+        // the previous owner can no longer administer the contract
+        let msg = ExecuteMsg::TransferOwnership {
+            owner: String::from("creator"),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_query_oracle_value_pays_fee_from_budget() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 100);
+
+        // the budget is empty
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::QueryOracleValue { callback: None };
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1000, "atestfet"));
+
+        // callers cannot attach their own funds
+        let info = mock_info("creator", &coins(100, "atestfet"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: String::from(TEST_ORACLE_ADDRESS),
+                    msg: to_binary(&OracleMsg::QueryOracleValue {}).unwrap(),
+                    funds: coins(100, "atestfet"),
+                },
+                ORACLE_REPLY_ID
+            )
+        );
+    }
+
+    #[test]
+    fn test_refresh_allowlist() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 0);
+
+        let msg = ExecuteMsg::QueryOracleValue { callback: None };
+        let info = mock_info("user", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        assert!(res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let add_msg = ExecuteMsg::AddAllowedCaller {
+            address: String::from("user"),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, add_msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AllowedCallers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let allowed: AllowedCallersResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![Addr::unchecked("user")], allowed.callers);
+
+        let info = mock_info("user", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let remove_msg = ExecuteMsg::RemoveAllowedCaller {
+            address: String::from("user"),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, remove_msg).unwrap();
+
+        let info = mock_info("user", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_refresh_rate_limit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.refresh_policy = Some(RefreshPolicy::RateLimit { interval: 60 });
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 0);
+
+        let msg = ExecuteMsg::QueryOracleValue { callback: None };
+        let info = mock_info("user", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+        // a second refresh within the interval is rejected
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(59);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert!(res.is_err());
+
+        // other callers have their own limit
+        let other_info = mock_info("other_user", &[]);
+        let _res = execute(deps.as_mut(), env.clone(), other_info, msg.clone()).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(1);
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // an interval past the largest timestamp allows no further refreshes
+        let policy = ExecuteMsg::SetRefreshPolicy {
+            refresh_policy: RefreshPolicy::RateLimit { interval: u64::MAX },
+        };
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            policy,
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(StdError::generic_err("Refresh rate limit exceeded"), err);
+    }
+
+    #[test]
+    fn test_cache_ttl() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.cache_ttl = Some(60);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 0);
+
+        // nothing cached yet
+        let msg = ExecuteMsg::QueryOracleValue { callback: None };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        let push_msg = ExecuteMsg::SetOracleValue {
+            value: OracleValue {
+                value: Uint128::from(100000u128),
+                decimals: Uint128::from(5u128),
+                timestamp: mock_env().block.time,
+            },
+        };
+        let oracle_info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+        let _res = execute(deps.as_mut(), mock_env(), oracle_info, push_msg).unwrap();
+
+        // the fresh value is served without calling the oracle
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(59);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "oracle-value" && attr.value == "100000"));
+
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(deps.as_mut(), env, info.clone(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+        let stats: StatsResponse = from_binary(&res).unwrap();
+        assert_eq!(CacheStats { hits: 1, misses: 2 }, stats.cache);
+
+        // a time to live past the largest timestamp keeps the value cached
+        let config =
+            ExecuteMsg::UpdateConfig(from_slice(br#"{"cache_ttl":18446744073709551615}"#).unwrap());
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), config).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+    }
+
+    fn oracle_reply(value: u128, decimals: u128, timestamp: u64) -> Reply {
+        Reply {
+            id: ORACLE_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm")
+                    .add_attribute("oracle-value", value.to_string())
+                    .add_attribute("decimals", decimals.to_string())
+                    .add_attribute("timestamp", timestamp.to_string())],
+                data: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_malformed_oracle_reply() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let initial = ORACLE_VALUE.load(&deps.storage).unwrap();
+
+        let malformed = [
+            ("oracle-value", "-1"),
+            ("decimals", ""),
+            ("timestamp", "yesterday"),
+            ("timestamp", "99999999999"),
+        ];
+        for (key, value) in malformed {
+            let mut event = Event::new("wasm");
+            for (attr, default) in [
+                ("oracle-value", "100"),
+                ("decimals", "5"),
+                ("timestamp", "1"),
+            ] {
+                event = event.add_attribute(attr, if attr == key { value } else { default });
+            }
+            let msg = Reply {
+                id: ORACLE_REPLY_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![event],
+                    data: None,
+                }),
+            };
+            let err = reply(deps.as_mut(), mock_env(), msg).unwrap_err();
+            assert_eq!(
+                StdError::generic_err(format!("invalid `{}` attribute: {}", key, value)),
+                err
+            );
+        }
+
+        // a missing attribute is rejected as well
+        let msg = Reply {
+            id: ORACLE_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("wasm").add_attribute("oracle-value", "100")],
+                data: None,
+            }),
+        };
+        let err = reply(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(
+            StdError::generic_err("cannot find `decimals` attribute"),
+            err
+        );
+        assert_eq!(initial, ORACLE_VALUE.load(&deps.storage).unwrap());
+    }
+
+    #[test]
+    fn test_oracle_value_callback() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 0);
+
+        let callback = Callback {
+            contract_addr: String::from("lending"),
+            msg: to_binary("update_price").unwrap(),
+        };
+        let msg = ExecuteMsg::QueryOracleValue {
+            callback: Some(callback.clone()),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+
+        // the stored value is forwarded to the callback contract
+        let res = reply(deps.as_mut(), mock_env(), oracle_reply(100000, 5, 1000)).unwrap();
+        let oracle_value = OracleValue {
+            value: Uint128::from(100000u128),
+            decimals: Uint128::from(5u128),
+            timestamp: Timestamp::from_seconds(1000),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: callback.contract_addr,
+                    msg: to_binary(&CallbackMsg::ReceiveOracleValue {
+                        oracle_value: oracle_value.clone(),
+                        msg: callback.msg,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                CALLBACK_REPLY_ID
+            )]
+        );
+        assert!(PENDING_CALLBACK.may_load(&deps.storage).unwrap().is_none());
+
+        // a failing callback is reported without reverting the stored value
+        let callback_reply = Reply {
+            id: CALLBACK_REPLY_ID,
+            result: SubMsgResult::Err(String::from("callback failed")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), callback_reply).unwrap();
+        assert_eq!("oracle-callback-failed", res.events[0].ty);
+
+        let stored_value = ORACLE_VALUE.load(&deps.storage).unwrap();
+        assert_eq!(oracle_value, stored_value);
+    }
+
+    #[test]
+    fn test_value_history() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let msg = init_msg(TEST_ORACLE_ADDRESS);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        for i in 1..=(MAX_VALUE_HISTORY + 2) {
+            let msg = ExecuteMsg::SetOracleValue {
+                value: OracleValue {
+                    value: Uint128::from(i as u128),
+                    decimals: Uint128::from(0u128),
+                    timestamp: Timestamp::from_seconds(i * 10),
+                },
+            };
+            let info = mock_info(TEST_ORACLE_ADDRESS, &[]);
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        // the oldest values were dropped
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ValueHistory {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+        let history: ValueHistoryResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![Timestamp::from_seconds(30), Timestamp::from_seconds(40)],
+            history
+                .values
+                .iter()
+                .map(|value| value.timestamp)
+                .collect::<Vec<_>>()
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ValueHistory {
+                start_after: Some(Timestamp::from_seconds(40)),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        let history: ValueHistoryResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(5u128), history.values[0].value);
+
+        // latest value at or before the given time
+        for (timestamp, expected) in [(55, Some(5u128)), (60, Some(6u128)), (20, None)] {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ValueAt {
+                    timestamp: Timestamp::from_seconds(timestamp),
+                },
+            )
+            .unwrap();
+            let value_at: ValueAtResponse = from_binary(&res).unwrap();
+            assert_eq!(
+                expected.map(Uint128::from),
+                value_at.oracle_value.map(|oracle_value| oracle_value.value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_catch_oracle_errors() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.catch_oracle_errors = Some(true);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        mock_oracle_fee(&mut deps, 0);
+
+        let msg = ExecuteMsg::QueryOracleValue {
+            callback: Some(Callback {
+                contract_addr: String::from("lending"),
+                msg: to_binary("update_price").unwrap(),
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(ReplyOn::Always, res.messages[0].reply_on);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::LastError {}).unwrap();
+        let last_error: LastErrorResponse = from_binary(&res).unwrap();
+        assert_eq!(None, last_error.last_error);
+
+        // the failure is recorded instead of reverting the transaction
+        let oracle_reply = Reply {
+            id: ORACLE_REPLY_ID,
+            result: SubMsgResult::Err(String::from("Insufficient funds sent")),
+        };
+        let res = reply(deps.as_mut(), mock_env(), oracle_reply).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!("status", res.attributes[0].key);
+        assert_eq!("oracle_error", res.attributes[0].value);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::LastError {}).unwrap();
+        let last_error: LastErrorResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            Some(OracleError {
+                error: String::from("Insufficient funds sent"),
+                timestamp: mock_env().block.time,
+            }),
+            last_error.last_error
+        );
+        assert!(PENDING_CALLBACK.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_smart_query_mode() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let mut msg = init_msg(TEST_ORACLE_ADDRESS);
+        msg.query_mode = Some(QueryMode::Smart {});
+        msg.max_age = Some(60);
+        let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        // the oracle answers the value query only, no fee is queried or paid
+        let timestamp = mock_env().block.time;
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == TEST_ORACLE_ADDRESS => {
+                match from_binary(msg).unwrap() {
+                    OracleQueryMsg::OracleValue {} => {
+                        let response = OracleValue {
+                            value: Uint128::from(1234u128),
+                            decimals: Uint128::from(5u128),
+                            timestamp,
+                        };
+                        SystemResult::Ok(ContractResult::Ok(to_binary(&response).unwrap()))
+                    }
+                    OracleQueryMsg::Fee {} => SystemResult::Err(SystemError::UnsupportedRequest {
+                        kind: String::from("fee"),
+                    }),
+                }
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: String::from("wasm"),
+            }),
+        });
+
+        // queries read the value from the oracle directly
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::OracleValue {}).unwrap();
+        let value: OracleValueResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(1234u128), value.oracle_value.value);
+        assert_eq!(timestamp, value.oracle_value.timestamp);
+
+        // and are subject to the configured checks
+        let mut late_env = mock_env();
+        late_env.block.time = timestamp.plus_seconds(61);
+        let res = query(deps.as_ref(), late_env, QueryMsg::OracleValue {});
+        assert!(res.is_err());
+
+        // refreshes by anyone are stored without a submessage
+        let msg = ExecuteMsg::QueryOracleValue {
+            callback: Some(Callback {
+                contract_addr: String::from("lending"),
+                msg: to_binary("update_price").unwrap(),
+            }),
+        };
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(ReplyOn::Error, res.messages[0].reply_on);
+        assert_eq!("oracle-value-update", res.events[0].ty);
+
+        let stored_value = ORACLE_VALUE.load(&deps.storage).unwrap();
+        assert_eq!(Uint128::from(1234u128), stored_value.value);
+
+        // reading the same value again is not logged as a rejected update
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "status" && attr.value == "unchanged"));
+        assert_eq!(0, REJECTED_UPDATE_COUNT.load(&deps.storage).unwrap());
+    }
 }
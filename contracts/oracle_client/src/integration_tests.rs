@@ -0,0 +1,483 @@
+#![cfg(test)]
+
+use cosmwasm_std::{
+    coins, Addr, Coin, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult, Timestamp,
+    Uint128,
+};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{ExecuteMsg, InstantiateMsg, LastErrorResponse, OracleValueResponse, QueryMsg};
+use crate::state::OracleValue;
+
+const OWNER: &str = "owner";
+const ORACLE_OWNER: &str = "oracle_owner";
+const DENOM: &str = "atestfet";
+const ORACLE_FEE: u128 = 100;
+
+// A small stand-in for an oracle contract, with the messages, fee handling and reply attributes
+// the client relies on. Swap in the entry points of your own oracle when extending the suite.
+pub mod mock_oracle {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        coins, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response,
+        StdError, StdResult, Uint128,
+    };
+    use cw_storage_plus::Item;
+
+    #[cw_serde]
+    pub struct InstantiateMsg {
+        pub fee: Uint128,
+    }
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        GrantOracleRole { address: Addr },
+        UpdateOracleValue { value: Uint128, decimals: Uint128 },
+        QueryOracleValue {},
+        WithdrawFees { amount: Uint128 },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        OracleRole {},
+        Fee {},
+        FeesAccrued {},
+    }
+
+    #[cw_serde]
+    pub struct OracleRoleResponse {
+        pub address: Addr,
+    }
+
+    #[cw_serde]
+    pub struct FeeResponse {
+        pub fee: Uint128,
+        pub denom: String,
+    }
+
+    #[cw_serde]
+    pub struct FeesAccruedResponse {
+        pub fees_accrued: Uint128,
+    }
+
+    #[cw_serde]
+    struct State {
+        owner: Addr,
+        oracle: Addr,
+        fee: Uint128,
+        fees_accrued: Uint128,
+        denom: String,
+        value: Uint128,
+        decimals: Uint128,
+        timestamp: u64,
+    }
+
+    const STATE: Item<State> = Item::new("state");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        let state = State {
+            owner: info.sender.clone(),
+            oracle: info.sender,
+            fee: msg.fee,
+            fees_accrued: Uint128::zero(),
+            denom: info.funds[0].denom.clone(),
+            value: Uint128::zero(),
+            decimals: Uint128::zero(),
+            timestamp: 0,
+        };
+        STATE.save(deps.storage, &state)?;
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        let mut state = STATE.load(deps.storage)?;
+        let mut res = Response::new();
+        match msg {
+            ExecuteMsg::GrantOracleRole { address } => {
+                if info.sender != state.owner {
+                    return Err(StdError::generic_err("Unauthorized"));
+                }
+                state.oracle = address;
+            }
+            ExecuteMsg::UpdateOracleValue { value, decimals } => {
+                state.value = value;
+                state.decimals = decimals;
+                state.timestamp = env.block.time.seconds();
+            }
+            ExecuteMsg::QueryOracleValue {} => {
+                let paid = info
+                    .funds
+                    .iter()
+                    .find(|coin| coin.denom == state.denom)
+                    .map(|coin| coin.amount)
+                    .unwrap_or_default();
+                if paid < state.fee {
+                    return Err(StdError::generic_err("Insufficient funds sent"));
+                }
+                state.fees_accrued += paid;
+                res = res
+                    .add_attribute("oracle-value", state.value)
+                    .add_attribute("decimals", state.decimals)
+                    .add_attribute("timestamp", state.timestamp.to_string());
+            }
+            ExecuteMsg::WithdrawFees { amount } => {
+                if info.sender != state.owner {
+                    return Err(StdError::generic_err("Unauthorized"));
+                }
+                state.fees_accrued = state.fees_accrued.checked_sub(amount)?;
+                res = res.add_message(BankMsg::Send {
+                    to_address: state.owner.to_string(),
+                    amount: coins(amount.u128(), &state.denom),
+                });
+            }
+        }
+        STATE.save(deps.storage, &state)?;
+        Ok(res)
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+        let state = STATE.load(deps.storage)?;
+        match msg {
+            QueryMsg::OracleRole {} => to_binary(&OracleRoleResponse {
+                address: state.oracle,
+            }),
+            QueryMsg::Fee {} => to_binary(&FeeResponse {
+                fee: state.fee,
+                denom: state.denom,
+            }),
+            QueryMsg::FeesAccrued {} => to_binary(&FeesAccruedResponse {
+                fees_accrued: state.fees_accrued,
+            }),
+        }
+    }
+}
+
+pub fn contract_oracle() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        mock_oracle::execute,
+        mock_oracle::instantiate,
+        mock_oracle::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_oracle_client() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+    Box::new(contract)
+}
+
+// An oracle and an oracle client wired together, with the client owner and the oracle owner
+// holding funds to pay for the fee budget and the oracle deposit
+pub struct OracleSuite {
+    pub app: App,
+    pub owner: Addr,
+    pub oracle_owner: Addr,
+    pub oracle: Addr,
+    pub client: Addr,
+}
+
+impl OracleSuite {
+    pub fn new(client_msg: impl FnOnce(String) -> InstantiateMsg) -> Self {
+        Self::with_oracle(contract_oracle(), client_msg)
+    }
+
+    pub fn with_oracle(
+        oracle_contract: Box<dyn Contract<Empty>>,
+        client_msg: impl FnOnce(String) -> InstantiateMsg,
+    ) -> Self {
+        let owner = Addr::unchecked(OWNER);
+        let oracle_owner = Addr::unchecked(ORACLE_OWNER);
+
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &owner, coins(10000, DENOM))
+                .unwrap();
+            router
+                .bank
+                .init_balance(storage, &oracle_owner, coins(1000, DENOM))
+                .unwrap();
+        });
+
+        let oracle_id = app.store_code(oracle_contract);
+        let oracle = app
+            .instantiate_contract(
+                oracle_id,
+                oracle_owner.clone(),
+                &mock_oracle::InstantiateMsg {
+                    fee: Uint128::from(ORACLE_FEE),
+                },
+                &coins(1, DENOM),
+                "oracle",
+                None,
+            )
+            .unwrap();
+
+        let client_id = app.store_code(contract_oracle_client());
+        let client = app
+            .instantiate_contract(
+                client_id,
+                owner.clone(),
+                &client_msg(oracle.to_string()),
+                &[],
+                "oracle client",
+                None,
+            )
+            .unwrap();
+
+        OracleSuite {
+            app,
+            owner,
+            oracle_owner,
+            oracle,
+            client,
+        }
+    }
+
+    pub fn fund_fee_budget(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                self.owner.clone(),
+                self.client.clone(),
+                &ExecuteMsg::FundFeeBudget {},
+                &coins(amount, DENOM),
+            )
+            .unwrap();
+    }
+
+    pub fn update_oracle_value(&mut self, sender: &Addr, value: u128, decimals: u128) {
+        self.app
+            .execute_contract(
+                sender.clone(),
+                self.oracle.clone(),
+                &mock_oracle::ExecuteMsg::UpdateOracleValue {
+                    value: Uint128::from(value),
+                    decimals: Uint128::from(decimals),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    pub fn refresh(&mut self, sender: &Addr) -> AppResponse {
+        self.app
+            .execute_contract(
+                sender.clone(),
+                self.client.clone(),
+                &ExecuteMsg::QueryOracleValue { callback: None },
+                &[],
+            )
+            .unwrap()
+    }
+
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.height += 1;
+            block.time = block.time.plus_seconds(seconds);
+        });
+    }
+
+    pub fn block_time(&self) -> Timestamp {
+        self.app.block_info().time
+    }
+
+    pub fn client_value(&self) -> OracleValue {
+        let res: OracleValueResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.client.clone(), &QueryMsg::OracleValue {})
+            .unwrap();
+        res.oracle_value
+    }
+
+    pub fn fees_accrued(&self) -> Uint128 {
+        let res: mock_oracle::FeesAccruedResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.oracle.clone(), &mock_oracle::QueryMsg::FeesAccrued {})
+            .unwrap();
+        res.fees_accrued
+    }
+
+    pub fn balance(&self, address: &Addr) -> Coin {
+        self.app.wrap().query_balance(address, DENOM).unwrap()
+    }
+}
+
+fn client_msg(oracle_contract_address: String) -> InstantiateMsg {
+    InstantiateMsg {
+        oracle_contract_address,
+        max_age: None,
+        required_decimals: None,
+        refresh_policy: None,
+        cache_ttl: None,
+        min_value: None,
+        max_value: None,
+        catch_oracle_errors: None,
+        query_mode: None,
+    }
+}
+
+#[test]
+fn refresh_pays_fee_and_stores_reply() {
+    let mut suite = OracleSuite::new(client_msg);
+    let oracle_owner = suite.oracle_owner.clone();
+    let owner = suite.owner.clone();
+
+    suite.fund_fee_budget(1000);
+    suite.advance_time(10);
+    suite.update_oracle_value(&oracle_owner, 100000, 5);
+
+    suite.refresh(&owner);
+
+    // the reply from the oracle is parsed and stored, its timestamp has whole seconds
+    assert_eq!(
+        OracleValue {
+            value: Uint128::from(100000u128),
+            decimals: Uint128::from(5u128),
+            timestamp: Timestamp::from_seconds(suite.block_time().seconds()),
+        },
+        suite.client_value()
+    );
+
+    // the fee is paid from the client fee budget
+    assert_eq!(Uint128::from(900u128), suite.balance(&suite.client).amount);
+    assert_eq!(
+        Uint128::from(ORACLE_FEE + 1),
+        suite.balance(&suite.oracle).amount
+    );
+    assert_eq!(Uint128::from(ORACLE_FEE), suite.fees_accrued());
+}
+
+#[test]
+fn refresh_fails_without_fee_budget() {
+    let mut suite = OracleSuite::new(client_msg);
+
+    let err = suite
+        .app
+        .execute_contract(
+            suite.owner.clone(),
+            suite.client.clone(),
+            &ExecuteMsg::QueryOracleValue { callback: None },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        StdError::generic_err("Insufficient fee budget"),
+        err.downcast().unwrap()
+    );
+}
+
+fn paused_oracle_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: mock_oracle::ExecuteMsg,
+) -> StdResult<Response> {
+    Err(StdError::generic_err("Oracle is paused"))
+}
+
+#[test]
+fn failed_oracle_call_is_recorded() {
+    let paused_oracle = Box::new(ContractWrapper::new(
+        paused_oracle_execute,
+        mock_oracle::instantiate,
+        mock_oracle::query,
+    ));
+    let mut suite =
+        OracleSuite::with_oracle(paused_oracle, |oracle_contract_address| InstantiateMsg {
+            catch_oracle_errors: Some(true),
+            ..client_msg(oracle_contract_address)
+        });
+    let owner = suite.owner.clone();
+
+    suite.fund_fee_budget(1000);
+    let res = suite.refresh(&owner);
+    assert!(res.events.iter().any(|event| event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "status" && attr.value == "oracle_error")));
+
+    let res: LastErrorResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(suite.client.clone(), &QueryMsg::LastError {})
+        .unwrap();
+    let last_error = res.last_error.unwrap();
+    assert_eq!(suite.block_time(), last_error.timestamp);
+
+    // the fee sent with the failed call stays in the fee budget
+    assert_eq!(Uint128::from(1000u128), suite.balance(&suite.client).amount);
+}
+
+#[test]
+fn oracle_owner_withdraws_fees() {
+    let mut suite = OracleSuite::new(client_msg);
+    let owner = suite.owner.clone();
+    let oracle_owner = suite.oracle_owner.clone();
+
+    suite.fund_fee_budget(1000);
+    suite.refresh(&owner);
+    suite.advance_time(10);
+    suite.update_oracle_value(&oracle_owner, 100000, 5);
+    suite.refresh(&owner);
+    assert_eq!(Uint128::from(2 * ORACLE_FEE), suite.fees_accrued());
+
+    // only the oracle owner can withdraw
+    let withdraw = mock_oracle::ExecuteMsg::WithdrawFees {
+        amount: Uint128::from(2 * ORACLE_FEE),
+    };
+    suite
+        .app
+        .execute_contract(owner, suite.oracle.clone(), &withdraw, &[])
+        .unwrap_err();
+    suite
+        .app
+        .execute_contract(oracle_owner.clone(), suite.oracle.clone(), &withdraw, &[])
+        .unwrap();
+
+    assert_eq!(Uint128::zero(), suite.fees_accrued());
+    assert_eq!(
+        Uint128::from(999 + 2 * ORACLE_FEE),
+        suite.balance(&oracle_owner).amount
+    );
+}
+
+#[test]
+fn oracle_role_change() {
+    let mut suite = OracleSuite::new(client_msg);
+    let owner = suite.owner.clone();
+    let oracle_owner = suite.oracle_owner.clone();
+    let new_oracle = Addr::unchecked("new_oracle");
+
+    // only the oracle owner can grant the oracle role
+    let grant = mock_oracle::ExecuteMsg::GrantOracleRole {
+        address: new_oracle.clone(),
+    };
+    suite
+        .app
+        .execute_contract(owner.clone(), suite.oracle.clone(), &grant, &[])
+        .unwrap_err();
+    suite
+        .app
+        .execute_contract(oracle_owner.clone(), suite.oracle.clone(), &grant, &[])
+        .unwrap();
+
+    let res: mock_oracle::OracleRoleResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(suite.oracle.clone(), &mock_oracle::QueryMsg::OracleRole {})
+        .unwrap();
+    assert_eq!(new_oracle, res.address);
+}
@@ -1,41 +1,217 @@
-use cosmwasm_std::Addr;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp};
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::state::OracleValue;
+use crate::state::{
+    CacheStats, OracleError, OracleValue, QueryMode, RefreshPolicy, RejectedUpdate,
+};
 
 pub type Uint128 = cosmwasm_std::Uint128;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct InstantiateMsg {
-    pub oracle_contract_address: Addr,
+    pub oracle_contract_address: String,
+    pub max_age: Option<u64>,
+    pub required_decimals: Option<Uint128>,
+    pub refresh_policy: Option<RefreshPolicy>,
+    pub cache_ttl: Option<u64>,
+    pub min_value: Option<Uint128>,
+    pub max_value: Option<Uint128>,
+    pub catch_oracle_errors: Option<bool>,
+    pub query_mode: Option<QueryMode>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
 pub enum ExecuteMsg {
+    QueryOracleValue { callback: Option<Callback> },
+    SetOracleValue { value: OracleValue },
+    UpdateConfig(UpdateConfigMsg),
+    TransferOwnership { owner: String },
+    FundFeeBudget {},
+    WithdrawFeeBudget { amount: Coin },
+    SetRefreshPolicy { refresh_policy: RefreshPolicy },
+    AddAllowedCaller { address: String },
+    RemoveAllowedCaller { address: String },
+}
+
+// Contract to notify once a refreshed oracle value is stored, `msg` is passed through unchanged
+#[cw_serde]
+pub struct Callback {
+    pub contract_addr: String,
+    pub msg: Binary,
+}
+
+// Message sent to callback contracts, which should handle it as a variant of their ExecuteMsg
+#[cw_serde]
+pub enum CallbackMsg {
+    ReceiveOracleValue {
+        oracle_value: OracleValue,
+        msg: Binary,
+    },
+}
+
+// Fields left out keep their current value, the optional limits are removed by setting them to null
+#[cw_serde]
+pub struct UpdateConfigMsg {
+    pub oracle_contract_address: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_age: Option<Option<u64>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub required_decimals: Option<Option<Uint128>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cache_ttl: Option<Option<u64>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_value: Option<Option<Uint128>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_value: Option<Option<Uint128>>,
+    pub catch_oracle_errors: Option<bool>,
+    pub query_mode: Option<QueryMode>,
+}
+
+// Reads an explicit null as Some(None), so it can be told apart from a field that is left out
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[cw_serde]
+pub enum OracleMsg {
     QueryOracleValue {},
 }
 
+#[cw_serde]
+pub enum OracleQueryMsg {
+    Fee {},
+    // only used in smart query mode, answered with an `OracleValue`
+    OracleValue {},
+}
+
+// response of another contract, so unknown fields are accepted
 #[derive(Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum OracleMsg {
-    QueryOracleValue { address: Addr },
+pub struct OracleFeeResponse {
+    pub fee: Uint128,
+    pub denom: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(OracleContractResponse)]
     OracleContract {},
+    #[returns(OracleValueResponse)]
     OracleValue {},
+    #[returns(FeeBudgetResponse)]
+    FeeBudget {},
+    #[returns(RefreshPolicyResponse)]
+    RefreshPolicy {},
+    #[returns(AllowedCallersResponse)]
+    AllowedCallers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(StatsResponse)]
+    Stats {},
+    #[returns(RejectedUpdatesResponse)]
+    RejectedUpdates {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(ValueHistoryResponse)]
+    ValueHistory {
+        start_after: Option<Timestamp>,
+        limit: Option<u32>,
+    },
+    #[returns(ValueAtResponse)]
+    ValueAt { timestamp: Timestamp },
+    #[returns(LastErrorResponse)]
+    LastError {},
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub oracle_contract_address: Addr,
+    pub max_age: Option<u64>,
+    pub required_decimals: Option<Uint128>,
+    pub cache_ttl: Option<u64>,
+    pub min_value: Option<Uint128>,
+    pub max_value: Option<Uint128>,
+    pub catch_oracle_errors: bool,
+    pub query_mode: QueryMode,
+}
+
+#[cw_serde]
 pub struct OracleContractResponse {
     pub address: Addr,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct OracleValueResponse {
     pub oracle_value: OracleValue,
 }
+
+#[cw_serde]
+pub struct FeeBudgetResponse {
+    pub budget: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct RefreshPolicyResponse {
+    pub refresh_policy: RefreshPolicy,
+}
+
+#[cw_serde]
+pub struct AllowedCallersResponse {
+    pub callers: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct StatsResponse {
+    pub cache: CacheStats,
+}
+
+#[cw_serde]
+pub struct RejectedUpdatesResponse {
+    pub updates: Vec<RejectedUpdate>,
+}
+
+#[cw_serde]
+pub struct ValueHistoryResponse {
+    pub values: Vec<OracleValue>,
+}
+
+#[cw_serde]
+pub struct ValueAtResponse {
+    pub oracle_value: Option<OracleValue>,
+}
+
+#[cw_serde]
+pub struct LastErrorResponse {
+    pub last_error: Option<OracleError>,
+}
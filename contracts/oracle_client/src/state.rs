@@ -2,7 +2,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Timestamp, Uint128};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::Callback;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct OracleValue {
@@ -11,11 +13,74 @@ pub struct OracleValue {
     pub timestamp: Timestamp,
 }
 
+// Who may trigger oracle refreshes paid from the fee budget, besides the owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshPolicy {
+    // only addresses on the allowlist
+    Allowlist {},
+    // anyone, at most once per `interval` seconds per caller
+    RateLimit { interval: u64 },
+}
+
+// How refreshed values are obtained from the oracle contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMode {
+    // execute the oracle's paid QueryOracleValue and read the value from the reply
+    Reply {},
+    // read the value synchronously with a free smart query
+    Smart {},
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub oracle_contract_address: Addr,
     pub owner: Addr,
+    // maximum age in seconds of accepted oracle values
+    pub max_age: Option<u64>,
+    pub required_decimals: Option<Uint128>,
+    pub refresh_policy: RefreshPolicy,
+    // number of seconds a refreshed value is served from the cache
+    pub cache_ttl: Option<u64>,
+    // bounds on accepted oracle values
+    pub min_value: Option<Uint128>,
+    pub max_value: Option<Uint128>,
+    // record failed oracle calls instead of reverting the transaction
+    pub catch_oracle_errors: bool,
+    pub query_mode: QueryMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OracleError {
+    pub error: String,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RejectedUpdate {
+    pub id: u64,
+    pub oracle_value: OracleValue,
+    pub reason: String,
+    pub received_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
 }
 
 pub const STATE: Item<State> = Item::new("state");
 pub const ORACLE_VALUE: Item<OracleValue> = Item::new("oracle_value");
+pub const LAST_UPDATED: Item<Timestamp> = Item::new("last_updated");
+pub const CACHE_STATS: Item<CacheStats> = Item::new("cache_stats");
+pub const ALLOWED_CALLERS: Map<&Addr, ()> = Map::new("allowed_callers");
+pub const LAST_REFRESH: Map<&Addr, Timestamp> = Map::new("last_refresh");
+pub const REJECTED_UPDATE_COUNT: Item<u64> = Item::new("rejected_update_count");
+pub const REJECTED_UPDATES: Map<u64, RejectedUpdate> = Map::new("rejected_updates");
+// stored oracle values keyed by their timestamp in seconds
+pub const VALUE_HISTORY: Map<u64, OracleValue> = Map::new("value_history");
+pub const VALUE_HISTORY_SIZE: Item<u64> = Item::new("value_history_size");
+pub const LAST_ERROR: Item<OracleError> = Item::new("last_error");
+pub const PENDING_CALLBACK: Item<Callback> = Item::new("pending_callback");
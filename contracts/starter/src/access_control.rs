@@ -0,0 +1,74 @@
+use cosmwasm_std::{Addr, Deps, Order, StdResult, Storage};
+use cw_storage_plus::{Bound, Map};
+
+use crate::error::ContractError;
+use crate::msg::{HasRoleResponse, RoleMembersResponse};
+
+// Members of this role can grant and revoke any role
+pub const ADMIN_ROLE: &str = "admin";
+
+// (role, member) pairs
+pub const ROLES: Map<(&str, &Addr), ()> = Map::new("roles");
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+pub fn has_role(storage: &dyn Storage, role: &str, address: &Addr) -> bool {
+    ROLES.has(storage, (role, address))
+}
+
+// Guard for execute handlers, fails unless `address` holds `role`
+pub fn ensure_role(storage: &dyn Storage, role: &str, address: &Addr) -> Result<(), ContractError> {
+    if !has_role(storage, role, address) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn grant_role(storage: &mut dyn Storage, role: &str, address: &Addr) -> StdResult<()> {
+    ROLES.save(storage, (role, address), &())
+}
+
+pub fn revoke_role(storage: &mut dyn Storage, role: &str, address: &Addr) {
+    ROLES.remove(storage, (role, address))
+}
+
+pub fn revoke_all(storage: &mut dyn Storage, role: &str) -> StdResult<()> {
+    let members = ROLES
+        .prefix(role)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for member in members {
+        revoke_role(storage, role, &member);
+    }
+    Ok(())
+}
+
+pub fn query_has_role(deps: Deps, role: String, address: String) -> StdResult<HasRoleResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(HasRoleResponse {
+        has_role: has_role(deps.storage, &role, &address),
+    })
+}
+
+pub fn query_role_members(
+    deps: Deps,
+    role: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RoleMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let members = ROLES
+        .prefix(&role)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RoleMembersResponse { members })
+}
@@ -1,29 +1,56 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
+use semver::Version;
 
+use crate::access_control::{
+    ensure_role, grant_role, query_has_role, query_role_members, revoke_all, revoke_role,
+    ADMIN_ROLE,
+};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, GetCountResponse, InstantiateMsg, QueryMsg};
-use crate::state::{State, STATE};
+use crate::events::CounterEvent;
+use crate::migrations::{run_migrations, MIGRATIONS};
+use crate::msg::{
+    AccountCount, BoundsResponse, ChangelogResponse, CountsResponse, ExecuteMsg, FrozenResponse,
+    GetCountOfResponse, GetCountResponse, InstantiateMsg, MigrateMsg, QueryMsg, RateLimit, SudoMsg,
+};
+use crate::rate_limit::{check_rate_limit, query_rate_limit_status, RATE_LIMIT};
+use crate::state::{
+    CounterChange, State, CHANGELOG, CHANGE_COUNT, COUNTS, COUNTS_BY_VALUE, FROZEN, STATE,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:starter";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// number of counter changes kept in the changelog
+const MAX_CHANGELOG: u64 = 100;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let state = State {
         count: msg.count,
-        owner: info.sender.clone(),
+        min: None,
+        max: None,
     };
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    STATE.save(deps.storage, &state)?;
+    STATE.save(deps.storage, &state, env.block.height)?;
+    record_change(deps.storage, &env, &info.sender, msg.count)?;
+    grant_role(deps.storage, ADMIN_ROLE, &info.sender)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
@@ -31,56 +58,443 @@ pub fn instantiate(
         .add_attribute("count", msg.count.to_string()))
 }
 
+// Every execute emits a `CounterEvent` and returns the new count as data
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let actor = info.sender.clone();
+    let action = action(&msg);
+    let old_count = STATE.load(deps.storage)?.count;
+    let res = dispatch(deps.branch(), env, info, msg)?;
+    let new_count = STATE.load(deps.storage)?.count;
+
+    let event = CounterEvent {
+        action: action.to_string(),
+        actor,
+        old_count,
+        new_count,
+    };
+    Ok(res.add_event(event.into()).set_data(to_binary(&new_count)?))
+}
+
+fn dispatch(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Increment {} => try_increment(deps),
-        ExecuteMsg::Reset { count } => try_reset(deps, info, count),
+        ExecuteMsg::Increment {} => try_increment(deps, env, info),
+        ExecuteMsg::Decrement {} => try_decrement(deps, env, info),
+        ExecuteMsg::IncrementBy { amount } => try_increment_by(deps, env, info, amount),
+        ExecuteMsg::Reset { count } => try_reset(deps, env, info, count),
+        ExecuteMsg::SetBounds { min, max } => try_set_bounds(deps, env, info, min, max),
+        ExecuteMsg::SetRateLimit { rate_limit } => try_set_rate_limit(deps, info, rate_limit),
+        ExecuteMsg::GrantRole { role, address } => try_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => try_revoke_role(deps, info, role, address),
+        ExecuteMsg::RenounceRole { role } => try_renounce_role(deps, info, role),
+        #[cfg(feature = "ibc")]
+        ExecuteMsg::IncrementRemote { channel_id } => {
+            crate::ibc::try_increment_remote(deps, env, info, channel_id)
+        }
     }
 }
 
-pub fn try_increment(deps: DepsMut) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        state.count += 1;
-        Ok(state)
-    })?;
+// Name of the executed message in the `CounterEvent`
+fn action(msg: &ExecuteMsg) -> &'static str {
+    match msg {
+        ExecuteMsg::Increment {} => "increment",
+        ExecuteMsg::Decrement {} => "decrement",
+        ExecuteMsg::IncrementBy { .. } => "increment_by",
+        ExecuteMsg::Reset { .. } => "reset",
+        ExecuteMsg::SetBounds { .. } => "set_bounds",
+        ExecuteMsg::SetRateLimit { .. } => "set_rate_limit",
+        ExecuteMsg::GrantRole { .. } => "grant_role",
+        ExecuteMsg::RevokeRole { .. } => "revoke_role",
+        ExecuteMsg::RenounceRole { .. } => "renounce_role",
+        #[cfg(feature = "ibc")]
+        ExecuteMsg::IncrementRemote { .. } => "increment_remote",
+    }
+}
 
+pub fn try_increment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    add_to_counts(deps.storage, &env, &info.sender, 1)?;
     Ok(Response::new().add_attribute("method", "try_increment"))
 }
 
-pub fn try_reset(deps: DepsMut, info: MessageInfo, count: i32) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if info.sender != state.owner {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.count = count;
-        Ok(state)
-    })?;
+pub fn try_decrement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    add_to_counts(deps.storage, &env, &info.sender, -1)?;
+    Ok(Response::new().add_attribute("method", "try_decrement"))
+}
+
+pub fn try_increment_by(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: i32,
+) -> Result<Response, ContractError> {
+    add_to_counts(deps.storage, &env, &info.sender, amount)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_increment_by")
+        .add_attribute("amount", amount.to_string()))
+}
+
+// Add `amount` to the global counter and the sender's counter
+pub(crate) fn add_to_counts(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    amount: i32,
+) -> Result<(), ContractError> {
+    if FROZEN.may_load(storage)?.unwrap_or(false) {
+        return Err(ContractError::Frozen {});
+    }
+    let mut state = STATE.load(storage)?;
+    state.count = state
+        .count
+        .checked_add(amount)
+        .ok_or(ContractError::Overflow {})?;
+    check_bounds(&state)?;
+    let count = COUNTS.may_load(storage, sender)?.unwrap_or(0);
+    let new_count = count
+        .checked_add(amount.into())
+        .ok_or(ContractError::Overflow {})?;
+    // checks come before any write, so a failed update leaves no trace even if not reverted
+    check_rate_limit(storage, env, sender)?;
+
+    STATE.save(storage, &state, env.block.height)?;
+    record_change(storage, env, sender, state.count)?;
+    save_count(storage, sender, count, new_count)?;
+    Ok(())
+}
+
+fn check_bounds(state: &State) -> Result<(), ContractError> {
+    let below_min = matches!(state.min, Some(min) if state.count < min);
+    let above_max = matches!(state.max, Some(max) if state.count > max);
+    if below_min || above_max {
+        return Err(ContractError::OutOfBounds { count: state.count });
+    }
+    Ok(())
+}
+
+// Update an account counter together with its entry in the ordered index
+fn save_count(storage: &mut dyn Storage, address: &Addr, old: i64, new: i64) -> StdResult<()> {
+    COUNTS_BY_VALUE.remove(storage, (old, address));
+    COUNTS_BY_VALUE.save(storage, (new, address), &())?;
+    COUNTS.save(storage, address, &new)
+}
+
+// Append a change of the global counter to the changelog, dropping the oldest one when full
+fn record_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+    count: i32,
+) -> StdResult<()> {
+    let id = CHANGE_COUNT.may_load(storage)?.unwrap_or(0);
+    CHANGELOG.save(
+        storage,
+        id,
+        &CounterChange {
+            id,
+            address: address.clone(),
+            count,
+            height: env.block.height,
+            time: env.block.time,
+        },
+    )?;
+    if id >= MAX_CHANGELOG {
+        CHANGELOG.remove(storage, id - MAX_CHANGELOG);
+    }
+    CHANGE_COUNT.save(storage, &(id + 1))
+}
+
+pub fn try_reset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    count: i32,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, ADMIN_ROLE, &info.sender)?;
+    let mut state = STATE.load(deps.storage)?;
+    state.count = count;
+    check_bounds(&state)?;
+    STATE.save(deps.storage, &state, env.block.height)?;
+    record_change(deps.storage, &env, &info.sender, count)?;
     Ok(Response::new().add_attribute("method", "reset"))
 }
 
+pub fn try_set_bounds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min: Option<i32>,
+    max: Option<i32>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, ADMIN_ROLE, &info.sender)?;
+    if let (Some(min), Some(max)) = (min, max) {
+        if min > max {
+            return Err(ContractError::InvalidBounds {});
+        }
+    }
+    // the current count has to be within the new bounds
+    let mut state = STATE.load(deps.storage)?;
+    state.min = min;
+    state.max = max;
+    check_bounds(&state)?;
+    STATE.save(deps.storage, &state, env.block.height)?;
+    Ok(Response::new().add_attribute("method", "set_bounds"))
+}
+
+pub fn try_set_rate_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    rate_limit: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, ADMIN_ROLE, &info.sender)?;
+    match rate_limit {
+        Some(rate_limit) => RATE_LIMIT.save(deps.storage, &rate_limit)?,
+        None => RATE_LIMIT.remove(deps.storage),
+    }
+    Ok(Response::new().add_attribute("method", "set_rate_limit"))
+}
+
+pub fn try_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, ADMIN_ROLE, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    grant_role(deps.storage, &role, &address)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role)
+        .add_attribute("address", address))
+}
+
+pub fn try_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, ADMIN_ROLE, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    revoke_role(deps.storage, &role, &address);
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role)
+        .add_attribute("address", address))
+}
+
+pub fn try_renounce_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &role, &info.sender)?;
+    revoke_role(deps.storage, &role, &info.sender);
+
+    Ok(Response::new()
+        .add_attribute("method", "renounce_role")
+        .add_attribute("role", role)
+        .add_attribute("address", info.sender))
+}
+
+// Only callable by chain governance, there is no sender to check
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
     match msg {
-        QueryMsg::GetCount {} => to_binary(&query_count(deps)?),
+        SudoMsg::ForceReset { count } => sudo_force_reset(deps, env, count),
+        SudoMsg::SetAdmin { address } => sudo_set_admin(deps, address),
+        SudoMsg::Freeze {} => sudo_set_frozen(deps, true),
+        SudoMsg::Unfreeze {} => sudo_set_frozen(deps, false),
     }
 }
 
-fn query_count(deps: Deps) -> StdResult<GetCountResponse> {
-    let state = STATE.load(deps.storage)?;
+pub fn sudo_force_reset(deps: DepsMut, env: Env, count: i32) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    state.count = count;
+    check_bounds(&state)?;
+    STATE.save(deps.storage, &state, env.block.height)?;
+    // governance changes are logged under the contract's own address
+    record_change(deps.storage, &env, &env.contract.address, count)?;
+    Ok(Response::new()
+        .add_attribute("method", "force_reset")
+        .add_attribute("count", count.to_string()))
+}
+
+pub fn sudo_set_admin(deps: DepsMut, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    revoke_all(deps.storage, ADMIN_ROLE)?;
+    grant_role(deps.storage, ADMIN_ROLE, &address)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_admin")
+        .add_attribute("address", address))
+}
+
+pub fn sudo_set_frozen(deps: DepsMut, frozen: bool) -> Result<Response, ContractError> {
+    FROZEN.save(deps.storage, &frozen)?;
+    Ok(Response::new().add_attribute("method", if frozen { "freeze" } else { "unfreeze" }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract {
+            contract: stored.contract,
+        });
+    }
+
+    let previous: Version = stored.version.parse()?;
+    let current: Version = CONTRACT_VERSION.parse()?;
+    if previous > current {
+        return Err(ContractError::CannotMigrate {
+            previous: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    let applied = run_migrations(deps.storage, &env, MIGRATIONS, &previous, &current)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", stored.version)
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetCount { at_height } => to_binary(&query_count(deps, at_height)?),
+        QueryMsg::Changelog { start_after, limit } => {
+            to_binary(&query_changelog(deps, start_after, limit)?)
+        }
+        QueryMsg::GetBounds {} => to_binary(&query_bounds(deps)?),
+        QueryMsg::GetCountOf { address } => to_binary(&query_count_of(deps, address)?),
+        QueryMsg::ListCounts { start_after, limit } => {
+            to_binary(&query_list_counts(deps, start_after, limit)?)
+        }
+        QueryMsg::TopCounts { limit } => to_binary(&query_top_counts(deps, limit)?),
+        QueryMsg::RateLimitStatus { address } => {
+            to_binary(&query_rate_limit_status(deps, env, address)?)
+        }
+        QueryMsg::IsFrozen {} => to_binary(&query_frozen(deps)?),
+        #[cfg(feature = "ibc")]
+        QueryMsg::RemoteConfirmed {} => to_binary(&crate::ibc::query_remote_confirmed(deps)?),
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+    }
+}
+
+fn query_count(deps: Deps, at_height: Option<u64>) -> StdResult<GetCountResponse> {
+    let state = match at_height {
+        Some(height) => STATE
+            .may_load_at_height(deps.storage, height)?
+            .ok_or_else(|| StdError::generic_err("No count at the given height"))?,
+        None => STATE.load(deps.storage)?,
+    };
     Ok(GetCountResponse { count: state.count })
 }
 
+fn query_changelog(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ChangelogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let changes = CHANGELOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ChangelogResponse { changes })
+}
+
+fn query_bounds(deps: Deps) -> StdResult<BoundsResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(BoundsResponse {
+        min: state.min,
+        max: state.max,
+    })
+}
+
+fn query_frozen(deps: Deps) -> StdResult<FrozenResponse> {
+    let frozen = FROZEN.may_load(deps.storage)?.unwrap_or(false);
+    Ok(FrozenResponse { frozen })
+}
+
+fn query_count_of(deps: Deps, address: String) -> StdResult<GetCountOfResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let count = COUNTS.may_load(deps.storage, &address)?.unwrap_or(0);
+    Ok(GetCountOfResponse { count })
+}
+
+fn query_list_counts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let counts = COUNTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, count)| AccountCount { address, count }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CountsResponse { counts })
+}
+
+fn query_top_counts(deps: Deps, limit: Option<u32>) -> StdResult<CountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let counts = COUNTS_BY_VALUE
+        .keys(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(count, address)| AccountCount { address, count }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CountsResponse { counts })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::access_control::has_role;
+    use crate::msg::{HasRoleResponse, RateLimitStatusResponse, RoleMembersResponse, Window};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
+    use cosmwasm_std::{coins, from_binary, Addr, Event};
 
     #[test]
     fn proper_initialization() {
@@ -94,7 +508,12 @@ mod tests {
         assert_eq!(0, res.messages.len());
 
         // it worked, let's query the state
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
         let value: GetCountResponse = from_binary(&res).unwrap();
         assert_eq!(17, value.count);
     }
@@ -113,11 +532,477 @@ mod tests {
         let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // should increase counter by 1
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
         let value: GetCountResponse = from_binary(&res).unwrap();
         assert_eq!(18, value.count);
     }
 
+    #[test]
+    fn decrement_and_increment_by() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::IncrementBy { amount: 5 };
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Decrement {}).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(21, value.count);
+
+        let msg = QueryMsg::GetCountOf {
+            address: String::from("anyone"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: GetCountOfResponse = from_binary(&res).unwrap();
+        assert_eq!(4, value.count);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: i32::MAX };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {});
+        match res {
+            Err(ContractError::Overflow {}) => {}
+            _ => panic!("Must return overflow error"),
+        }
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(i32::MAX, value.count);
+    }
+
+    #[test]
+    fn counter_event() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::IncrementBy { amount: 3 };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(Some(to_binary(&20).unwrap()), res.data);
+        let event = Event::new("counter")
+            .add_attribute("action", "increment_by")
+            .add_attribute("actor", "anyone")
+            .add_attribute("old_count", "17")
+            .add_attribute("new_count", "20");
+        assert_eq!(vec![event], res.events);
+
+        // executes that leave the count unchanged report it as well
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetBounds {
+            min: None,
+            max: Some(30),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(Some(to_binary(&20).unwrap()), res.data);
+        assert_eq!("20", res.events[0].attributes[2].value);
+        assert_eq!("20", res.events[0].attributes[3].value);
+    }
+
+    #[test]
+    fn history() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let start = env.block.height;
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        env.block.height += 1;
+        let info = mock_info("anyone", &[]);
+        let _res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Increment {}).unwrap();
+
+        env.block.height += 1;
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Reset { count: 5 };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // values are read at the start of the given block
+        for (height, count) in [(start + 1, 17), (start + 2, 18), (start + 3, 5)] {
+            let msg = QueryMsg::GetCount {
+                at_height: Some(height),
+            };
+            let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let value: GetCountResponse = from_binary(&res).unwrap();
+            assert_eq!(count, value.count);
+        }
+
+        // the contract did not exist yet
+        let msg = QueryMsg::GetCount {
+            at_height: Some(start),
+        };
+        assert!(query(deps.as_ref(), env.clone(), msg).is_err());
+
+        let msg = QueryMsg::Changelog {
+            start_after: Some(0),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: ChangelogResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![
+                CounterChange {
+                    id: 1,
+                    address: Addr::unchecked("anyone"),
+                    count: 18,
+                    height: start + 1,
+                    time: env.block.time,
+                },
+                CounterChange {
+                    id: 2,
+                    address: Addr::unchecked("creator"),
+                    count: 5,
+                    height: start + 2,
+                    time: env.block.time,
+                },
+            ],
+            value.changes
+        );
+    }
+
+    #[test]
+    fn changelog_is_capped() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 0 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for _ in 0..MAX_CHANGELOG {
+            let info = mock_info("anyone", &[]);
+            let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {}).unwrap();
+        }
+
+        // the oldest changes are dropped once the log is full
+        let msg = QueryMsg::Changelog {
+            start_after: None,
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ChangelogResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.changes[0].id);
+        assert_eq!(
+            MAX_CHANGELOG,
+            CHANGELOG
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count() as u64
+        );
+    }
+
+    #[test]
+    fn bounds() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 5 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only admins can set bounds
+        let msg = ExecuteMsg::SetBounds {
+            min: Some(0),
+            max: Some(6),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+        let admin = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetBounds {}).unwrap();
+        let value: BoundsResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(0), value.min);
+        assert_eq!(Some(6), value.max);
+
+        // bounds have to be ordered and contain the current count
+        let msg = ExecuteMsg::SetBounds {
+            min: Some(3),
+            max: Some(2),
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin.clone(), msg);
+        match res {
+            Err(ContractError::InvalidBounds {}) => {}
+            _ => panic!("Must return invalid bounds error"),
+        }
+        let msg = ExecuteMsg::SetBounds {
+            min: Some(10),
+            max: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), admin.clone(), msg);
+        match res {
+            Err(ContractError::OutOfBounds { count: 5 }) => {}
+            _ => panic!("Must return out of bounds error"),
+        }
+
+        // every change is checked against the bounds
+        let info = mock_info("anyone", &[]);
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        );
+        match res {
+            Err(ContractError::OutOfBounds { count: 7 }) => {}
+            _ => panic!("Must return out of bounds error"),
+        }
+        let msg = ExecuteMsg::IncrementBy { amount: -7 };
+        assert!(execute(deps.as_mut(), mock_env(), info, msg).is_err());
+        let msg = ExecuteMsg::Reset { count: -1 };
+        assert!(execute(deps.as_mut(), mock_env(), admin, msg).is_err());
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(6, value.count);
+    }
+
+    #[test]
+    fn rate_limit() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 0 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only admins can set a rate limit
+        let msg = ExecuteMsg::SetRateLimit {
+            rate_limit: Some(RateLimit {
+                limit: 2,
+                window: Window::Blocks(10),
+            }),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // two updates per address within 10 blocks
+        let mut env = mock_env();
+        let start = env.block.height;
+        let info = mock_info("anyone", &[]);
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        )
+        .unwrap();
+        env.block.height = start + 5;
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Decrement {},
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        );
+        match res {
+            Err(ContractError::RateLimited { limit: 2 }) => {}
+            _ => panic!("Must return rate limited error"),
+        }
+
+        // other addresses have their own window
+        let other = mock_info("other", &[]);
+        let _res = execute(deps.as_mut(), env.clone(), other, ExecuteMsg::Increment {}).unwrap();
+
+        let msg = QueryMsg::RateLimitStatus {
+            address: String::from("anyone"),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let value: RateLimitStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(2, value.used);
+        assert_eq!(Some(0), value.remaining);
+
+        // the first update leaves the window after 10 blocks
+        env.block.height = start + 10;
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: RateLimitStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(1), value.remaining);
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        )
+        .unwrap();
+
+        // removing the limit allows any number of updates
+        let msg = ExecuteMsg::SetRateLimit { rate_limit: None };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        for _ in 0..5 {
+            let _res = execute(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                ExecuteMsg::Increment {},
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn rate_limit_with_unbounded_window() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 0 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // windows too large to end are accepted and never release updates
+        for (sender, window) in [
+            ("alice", Window::Blocks(u64::MAX)),
+            ("bob", Window::Seconds(u64::MAX)),
+        ] {
+            let msg = ExecuteMsg::SetRateLimit {
+                rate_limit: Some(RateLimit { limit: 1, window }),
+            };
+            let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+            let info = mock_info(sender, &[]);
+            let _res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::Increment {},
+            )
+            .unwrap();
+            let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {});
+            match res {
+                Err(ContractError::RateLimited { limit: 1 }) => {}
+                _ => panic!("Must return rate limited error"),
+            }
+        }
+    }
+
+    #[test]
+    fn account_counts() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 0 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (sender, times) in [("alice", 2), ("bob", 3), ("carol", 1)] {
+            for _ in 0..times {
+                let info = mock_info(sender, &[]);
+                let _res =
+                    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {}).unwrap();
+            }
+        }
+
+        // the global counter counts every increment
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(6, value.count);
+
+        let msg = QueryMsg::GetCountOf {
+            address: String::from("bob"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: GetCountOfResponse = from_binary(&res).unwrap();
+        assert_eq!(3, value.count);
+
+        let msg = QueryMsg::GetCountOf {
+            address: String::from("dave"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: GetCountOfResponse = from_binary(&res).unwrap();
+        assert_eq!(0, value.count);
+
+        let account = |address: &str, count: i64| AccountCount {
+            address: Addr::unchecked(address),
+            count,
+        };
+
+        // accounts are listed by address
+        let msg = QueryMsg::ListCounts {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: CountsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![account("alice", 2), account("bob", 3)], value.counts);
+
+        let msg = QueryMsg::ListCounts {
+            start_after: Some(String::from("bob")),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: CountsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![account("carol", 1)], value.counts);
+
+        // and ranked by count
+        let msg = QueryMsg::TopCounts { limit: Some(2) };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: CountsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![account("bob", 3), account("alice", 2)], value.counts);
+    }
+
     #[test]
     fn reset() {
         let mut deps = mock_dependencies();
@@ -141,7 +1026,226 @@ mod tests {
         let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
 
         // should now be 5
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(5, value.count);
+    }
+
+    #[test]
+    fn roles() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only admins can grant roles
+        let msg = ExecuteMsg::GrantRole {
+            role: ADMIN_ROLE.to_string(),
+            address: String::from("anyone"),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = QueryMsg::HasRole {
+            role: ADMIN_ROLE.to_string(),
+            address: String::from("anyone"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let value: HasRoleResponse = from_binary(&res).unwrap();
+        assert!(value.has_role);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoleMembers {
+                role: ADMIN_ROLE.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: RoleMembersResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![Addr::unchecked("anyone"), Addr::unchecked("creator")],
+            value.members
+        );
+
+        // the new admin can reset the counter
+        let reset = ExecuteMsg::Reset { count: 5 };
+        let info = mock_info("anyone", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), reset.clone()).unwrap();
+
+        // until the role is renounced
+        let renounce = ExecuteMsg::RenounceRole {
+            role: ADMIN_ROLE.to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), renounce.clone()).unwrap();
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: HasRoleResponse = from_binary(&res).unwrap();
+        assert!(!value.has_role);
+        assert!(execute(deps.as_mut(), mock_env(), info.clone(), reset).is_err());
+        assert!(execute(deps.as_mut(), mock_env(), info, renounce).is_err());
+
+        // revoked roles are gone
+        let grant = ExecuteMsg::GrantRole {
+            role: String::from("minter"),
+            address: String::from("anyone"),
+        };
+        let revoke = ExecuteMsg::RevokeRole {
+            role: String::from("minter"),
+            address: String::from("anyone"),
+        };
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), grant).unwrap();
+        assert!(has_role(
+            &deps.storage,
+            "minter",
+            &Addr::unchecked("anyone")
+        ));
+        let _res = execute(deps.as_mut(), mock_env(), info, revoke).unwrap();
+        assert!(!has_role(
+            &deps.storage,
+            "minter",
+            &Addr::unchecked("anyone")
+        ));
+    }
+
+    #[test]
+    fn sudo() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // governance can freeze the counter
+        let _res = super::sudo(deps.as_mut(), mock_env(), SudoMsg::Freeze {}).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::IsFrozen {}).unwrap();
+        let value: FrozenResponse = from_binary(&res).unwrap();
+        assert!(value.frozen);
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Increment {},
+        );
+        match res {
+            Err(ContractError::Frozen {}) => {}
+            _ => panic!("Must return frozen error"),
+        }
+
+        // and reset it without being an admin, within the bounds
+        let msg = SudoMsg::ForceReset { count: 3 };
+        let _res = super::sudo(deps.as_mut(), mock_env(), msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(3, value.count);
+
+        let _res = super::sudo(deps.as_mut(), mock_env(), SudoMsg::Unfreeze {}).unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Increment {}).unwrap();
+
+        // setting the admin replaces the existing ones
+        let msg = SudoMsg::SetAdmin {
+            address: String::from("council"),
+        };
+        let _res = super::sudo(deps.as_mut(), mock_env(), msg).unwrap();
+        let creator = Addr::unchecked("creator");
+        assert!(!has_role(&deps.storage, ADMIN_ROLE, &creator));
+        let council = Addr::unchecked("council");
+        assert!(has_role(&deps.storage, ADMIN_ROLE, &council));
+    }
+
+    #[test]
+    fn migrate() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg { count: 17 };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // migrating to the same version keeps the state
+        let res = super::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(CONTRACT_VERSION, res.attributes[2].value);
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(17, value.count);
+
+        // cannot downgrade
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let res = super::migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res {
+            Err(ContractError::CannotMigrate { .. }) => {}
+            _ => panic!("Must return cannot migrate error"),
+        }
+
+        // cannot migrate another contract
+        set_contract_version(deps.as_mut().storage, "crates.io:other", "0.0.1").unwrap();
+        let res = super::migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res {
+            Err(ContractError::WrongContract { .. }) => {}
+            _ => panic!("Must return wrong contract error"),
+        }
+    }
+
+    #[test]
+    fn migrate_from_0_1_0() {
+        let mut deps = mock_dependencies();
+
+        // storage as left by instantiating version 0.1.0
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        deps.storage
+            .set(b"state", br#"{"count":17,"owner":"creator"}"#);
+
+        let res = super::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!("0.2.0", res.attributes[3].value);
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(17, value.count);
+
+        // the former owner is an admin and can still reset the count
+        let creator = Addr::unchecked("creator");
+        assert!(has_role(&deps.storage, ADMIN_ROLE, &creator));
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::Reset { count: 5 };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
         let value: GetCountResponse = from_binary(&res).unwrap();
         assert_eq!(5, value.count);
     }
@@ -9,8 +9,47 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Overflow")]
+    Overflow {},
+
+    #[error("Count {count} is out of bounds")]
+    OutOfBounds { count: i32 },
+
+    #[error("Invalid bounds, min is greater than max")]
+    InvalidBounds {},
+
+    #[error("Counter is frozen")]
+    Frozen {},
+
+    #[error("Rate limit of {limit} updates per window reached")]
+    RateLimited { limit: u32 },
+
+    #[error("Only unordered channels are supported")]
+    OnlyUnorderedChannel {},
+
+    #[error("Invalid IBC channel version {version}")]
+    InvalidIbcVersion { version: String },
+
+    #[error("Unknown channel {channel_id}")]
+    UnknownChannel { channel_id: String },
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+
+    #[error("Cannot migrate from contract {contract}")]
+    WrongContract { contract: String },
+
+    #[error("Cannot migrate from newer version {previous} to {current}")]
+    CannotMigrate { previous: String, current: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
@@ -0,0 +1,19 @@
+use cosmwasm_std::{Addr, Event};
+
+// Emitted by every execute, indexers see it as a `wasm-counter` event
+pub struct CounterEvent {
+    pub action: String,
+    pub actor: Addr,
+    pub old_count: i32,
+    pub new_count: i32,
+}
+
+impl From<CounterEvent> for Event {
+    fn from(event: CounterEvent) -> Self {
+        Event::new("counter")
+            .add_attribute("action", event.action)
+            .add_attribute("actor", event.actor)
+            .add_attribute("old_count", event.old_count.to_string())
+            .add_attribute("new_count", event.new_count.to_string())
+    }
+}
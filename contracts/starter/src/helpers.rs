@@ -0,0 +1,64 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Addr, CosmosMsg, QuerierWrapper, StdResult, WasmMsg, WasmQuery};
+use serde::de::DeserializeOwned;
+
+use crate::msg::{ExecuteMsg, GetCountResponse, QueryMsg};
+
+/// CounterContract is a wrapper around Addr that provides helpers for calling this
+/// contract from other contracts.
+#[cw_serde]
+pub struct CounterContract(pub Addr);
+
+impl CounterContract {
+    pub fn addr(&self) -> Addr {
+        self.0.clone()
+    }
+
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&msg.into())?;
+        Ok(WasmMsg::Execute {
+            contract_addr: self.addr().into(),
+            msg,
+            funds: vec![],
+        }
+        .into())
+    }
+
+    pub fn query<T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        req: QueryMsg,
+    ) -> StdResult<T> {
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_binary(&req)?,
+        }
+        .into();
+        querier.query(&query)
+    }
+
+    /*** messages ***/
+
+    pub fn increment(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Increment {})
+    }
+
+    pub fn reset(&self, count: i32) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Reset { count })
+    }
+
+    /*** queries ***/
+
+    pub fn count(&self, querier: &QuerierWrapper) -> StdResult<i32> {
+        let res: GetCountResponse = self.query(querier, QueryMsg::GetCount { at_height: None })?;
+        Ok(res.count)
+    }
+
+    pub fn count_at_height(&self, querier: &QuerierWrapper, height: u64) -> StdResult<i32> {
+        let req = QueryMsg::GetCount {
+            at_height: Some(height),
+        };
+        let res: GetCountResponse = self.query(querier, req)?;
+        Ok(res.count)
+    }
+}
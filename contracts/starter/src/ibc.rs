@@ -0,0 +1,323 @@
+use cosmwasm_schema::cw_serde;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, Deps, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcOrder, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo,
+    Response, StdResult,
+};
+use cw_storage_plus::{Item, Map};
+
+use crate::contract::add_to_counts;
+use crate::error::ContractError;
+use crate::msg::RemoteConfirmedResponse;
+use crate::state::STATE;
+
+pub const IBC_VERSION: &str = "counter-1";
+
+// how long a packet may take to be relayed, in seconds
+const PACKET_LIFETIME: u64 = 60 * 60;
+
+// Connected channels, keyed by our channel id
+pub const CHANNELS: Map<&str, IbcChannel> = Map::new("channels");
+
+// Remote increments acknowledged by the counterparty
+pub const REMOTE_CONFIRMED: Item<u64> = Item::new("remote_confirmed");
+
+// Data of the packets sent between counters
+#[cw_serde]
+pub enum CounterPacket {
+    Increment {},
+}
+
+// Acknowledgement of a packet, the result holds the new remote count
+#[cw_serde]
+pub enum CounterAck {
+    Result(Binary),
+    Error(String),
+}
+
+fn ack_success(count: i32) -> StdResult<Binary> {
+    to_binary(&CounterAck::Result(to_binary(&count)?))
+}
+
+fn ack_fail(err: String) -> StdResult<Binary> {
+    to_binary(&CounterAck::Error(err))
+}
+
+pub fn try_increment_remote(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    channel_id: String,
+) -> Result<Response, ContractError> {
+    if !CHANNELS.has(deps.storage, &channel_id) {
+        return Err(ContractError::UnknownChannel { channel_id });
+    }
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_binary(&CounterPacket::Increment {})?,
+        timeout: env.block.time.plus_seconds(PACKET_LIFETIME).into(),
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("method", "try_increment_remote")
+        .add_attribute("channel_id", channel_id))
+}
+
+pub fn query_remote_confirmed(deps: Deps) -> StdResult<RemoteConfirmedResponse> {
+    let count = REMOTE_CONFIRMED.may_load(deps.storage)?.unwrap_or(0);
+    Ok(RemoteConfirmedResponse { count })
+}
+
+fn check_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::OnlyUnorderedChannel {});
+    }
+    if channel.version != IBC_VERSION {
+        return Err(ContractError::InvalidIbcVersion {
+            version: channel.version.clone(),
+        });
+    }
+    if let Some(version) = counterparty_version {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: version.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    check_channel(msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    check_channel(channel, msg.counterparty_version())?;
+    CHANNELS.save(deps.storage, &channel.endpoint.channel_id, channel)?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    CHANNELS.remove(deps.storage, &channel.endpoint.channel_id);
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("method", "ibc_channel_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+// Remote increments are counted under the contract's own address
+fn receive_increment(deps: DepsMut, env: &Env, packet: &IbcPacket) -> Result<i32, ContractError> {
+    let CounterPacket::Increment {} = from_binary(&packet.data)?;
+    add_to_counts(deps.storage, env, &env.contract.address, 1)?;
+    Ok(STATE.load(deps.storage)?.count)
+}
+
+// Errors are returned to the sender in the acknowledgement instead of failing the transaction
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let res = IbcReceiveResponse::new().add_attribute("method", "ibc_packet_receive");
+    match receive_increment(deps, &env, &msg.packet) {
+        Ok(count) => Ok(res
+            .set_ack(ack_success(count)?)
+            .add_attribute("count", count.to_string())),
+        Err(err) => Ok(res
+            .set_ack(ack_fail(err.to_string())?)
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let res = IbcBasicResponse::new().add_attribute("method", "ibc_packet_ack");
+    match from_binary(&msg.acknowledgement.data)? {
+        CounterAck::Result(_) => {
+            let confirmed = REMOTE_CONFIRMED.may_load(deps.storage)?.unwrap_or(0);
+            REMOTE_CONFIRMED.save(deps.storage, &(confirmed + 1))?;
+            Ok(res.add_attribute("success", "true"))
+        }
+        CounterAck::Error(err) => Ok(res
+            .add_attribute("success", "false")
+            .add_attribute("error", err)),
+    }
+}
+
+// Nothing was changed when sending, so there is nothing to undo
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_timeout"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, GetCountResponse, InstantiateMsg, QueryMsg};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_connect_ack, mock_ibc_channel_open_init,
+        mock_ibc_channel_open_try, mock_ibc_packet_ack, mock_ibc_packet_recv,
+        mock_ibc_packet_timeout, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{CosmosMsg, IbcAcknowledgement, OwnedDeps};
+
+    const CHANNEL: &str = "channel-0";
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg { count: 0 };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        let _res = ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+        deps
+    }
+
+    #[test]
+    fn channel_handshake() {
+        let mut deps = mock_dependencies();
+
+        let msg = mock_ibc_channel_open_init(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+
+        let msg = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Ordered, IBC_VERSION);
+        match ibc_channel_open(deps.as_mut(), mock_env(), msg) {
+            Err(ContractError::OnlyUnorderedChannel {}) => {}
+            _ => panic!("Must return only unordered channel error"),
+        }
+        let msg = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, "other-1");
+        match ibc_channel_open(deps.as_mut(), mock_env(), msg) {
+            Err(ContractError::InvalidIbcVersion { .. }) => {}
+            _ => panic!("Must return invalid version error"),
+        }
+
+        let msg = mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        let _res = ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(CHANNELS.has(&deps.storage, CHANNEL));
+    }
+
+    #[test]
+    fn increment_remote() {
+        let mut deps = setup();
+
+        let msg = ExecuteMsg::IncrementRemote {
+            channel_id: String::from("channel-9"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg);
+        match res {
+            Err(ContractError::UnknownChannel { .. }) => {}
+            _ => panic!("Must return unknown channel error"),
+        }
+
+        let msg = ExecuteMsg::IncrementRemote {
+            channel_id: String::from(CHANNEL),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        match &res.messages[0].msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id, data, ..
+            }) => {
+                assert_eq!(CHANNEL, channel_id);
+                let packet: CounterPacket = from_binary(data).unwrap();
+                assert_eq!(CounterPacket::Increment {}, packet);
+            }
+            _ => panic!("Must send a packet"),
+        }
+
+        // only successful acks are confirmed
+        let packet = CounterPacket::Increment {};
+        let ack = IbcAcknowledgement::new(ack_success(1).unwrap());
+        let msg = mock_ibc_packet_ack(CHANNEL, &packet, ack).unwrap();
+        let _res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack = IbcAcknowledgement::new(ack_fail(String::from("Counter is frozen")).unwrap());
+        let msg = mock_ibc_packet_ack(CHANNEL, &packet, ack).unwrap();
+        let _res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        let msg = mock_ibc_packet_timeout(CHANNEL, &packet).unwrap();
+        let _res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::RemoteConfirmed {}).unwrap();
+        let value: RemoteConfirmedResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.count);
+    }
+
+    #[test]
+    fn receive_increment() {
+        let mut deps = setup();
+
+        let msg = mock_ibc_packet_recv(CHANNEL, &CounterPacket::Increment {}).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: CounterAck = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(CounterAck::Result(to_binary(&1).unwrap()), ack);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.count);
+
+        // failures are acknowledged as errors and change nothing
+        let msg = ExecuteMsg::SetBounds {
+            min: None,
+            max: Some(1),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let msg = mock_ibc_packet_recv(CHANNEL, &CounterPacket::Increment {}).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack: CounterAck = from_binary(&res.acknowledgement).unwrap();
+        assert_eq!(
+            CounterAck::Error(String::from("Count 2 is out of bounds")),
+            ack
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetCount { at_height: None },
+        )
+        .unwrap();
+        let value: GetCountResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.count);
+    }
+}
@@ -0,0 +1,252 @@
+#![cfg(test)]
+
+use cosmwasm_std::{attr, to_binary, Addr, Empty};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+use crate::access_control::ADMIN_ROLE;
+use crate::helpers::CounterContract;
+use crate::msg::{ExecuteMsg, GetCountOfResponse, InstantiateMsg, QueryMsg, SudoMsg};
+
+const ADMIN: &str = "admin";
+const USER: &str = "user";
+
+// A second contract that drives the counter through `CounterContract`
+pub mod caller {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{
+        to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    };
+
+    use crate::helpers::CounterContract;
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        IncrementCounter { counter: String },
+        ResetCounter { counter: String, count: i32 },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        CounterCount { counter: String },
+    }
+
+    pub fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::default())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        let message = match msg {
+            ExecuteMsg::IncrementCounter { counter } => {
+                CounterContract(deps.api.addr_validate(&counter)?).increment()?
+            }
+            ExecuteMsg::ResetCounter { counter, count } => {
+                CounterContract(deps.api.addr_validate(&counter)?).reset(count)?
+            }
+        };
+        Ok(Response::new().add_message(message))
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::CounterCount { counter } => {
+                let counter = CounterContract(deps.api.addr_validate(&counter)?);
+                to_binary(&counter.count(&deps.querier)?)
+            }
+        }
+    }
+}
+
+fn contract_counter() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::contract::sudo);
+    Box::new(contract)
+}
+
+fn contract_caller() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(caller::execute, caller::instantiate, caller::query);
+    Box::new(contract)
+}
+
+fn proper_instantiate() -> (App, CounterContract, Addr) {
+    let mut app = App::default();
+
+    let counter_id = app.store_code(contract_counter());
+    let counter_addr = app
+        .instantiate_contract(
+            counter_id,
+            Addr::unchecked(ADMIN),
+            &InstantiateMsg { count: 1 },
+            &[],
+            "counter",
+            None,
+        )
+        .unwrap();
+
+    let caller_id = app.store_code(contract_caller());
+    let caller_addr = app
+        .instantiate_contract(
+            caller_id,
+            Addr::unchecked(ADMIN),
+            &Empty {},
+            &[],
+            "caller",
+            None,
+        )
+        .unwrap();
+
+    (app, CounterContract(counter_addr), caller_addr)
+}
+
+#[test]
+fn increment_from_contract() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    let msg = caller::ExecuteMsg::IncrementCounter {
+        counter: counter.addr().into(),
+    };
+    app.execute_contract(Addr::unchecked(USER), caller.clone(), &msg, &[])
+        .unwrap();
+
+    assert_eq!(2, counter.count(&app.wrap()).unwrap());
+
+    // the calling contract is the sender of the increment
+    let res: GetCountOfResponse = counter
+        .query(
+            &app.wrap(),
+            QueryMsg::GetCountOf {
+                address: caller.into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(1, res.count);
+}
+
+#[test]
+fn counter_event_and_data() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    // the event of the nested call names the calling contract as the actor
+    let msg = caller::ExecuteMsg::IncrementCounter {
+        counter: counter.addr().into(),
+    };
+    let res = app
+        .execute_contract(Addr::unchecked(USER), caller.clone(), &msg, &[])
+        .unwrap();
+    let event = res.events.iter().find(|e| e.ty == "wasm-counter").unwrap();
+    assert!(event.attributes.contains(&attr("actor", caller.as_str())));
+    assert!(event.attributes.contains(&attr("old_count", "1")));
+    assert!(event.attributes.contains(&attr("new_count", "2")));
+
+    let msg = ExecuteMsg::Increment {};
+    let res = app
+        .execute_contract(Addr::unchecked(USER), counter.addr(), &msg, &[])
+        .unwrap();
+    assert_eq!(Some(to_binary(&3).unwrap()), res.data);
+}
+
+#[test]
+fn query_from_contract() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    let msg = ExecuteMsg::Increment {};
+    app.execute_contract(Addr::unchecked(USER), counter.addr(), &msg, &[])
+        .unwrap();
+
+    let count: i32 = app
+        .wrap()
+        .query_wasm_smart(
+            caller,
+            &caller::QueryMsg::CounterCount {
+                counter: counter.addr().into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(2, count);
+}
+
+#[test]
+fn reset_from_contract_requires_admin() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    let msg = caller::ExecuteMsg::ResetCounter {
+        counter: counter.addr().into(),
+        count: 10,
+    };
+    app.execute_contract(Addr::unchecked(USER), caller.clone(), &msg, &[])
+        .unwrap_err();
+
+    let grant = ExecuteMsg::GrantRole {
+        role: ADMIN_ROLE.to_string(),
+        address: caller.to_string(),
+    };
+    app.execute_contract(Addr::unchecked(ADMIN), counter.addr(), &grant, &[])
+        .unwrap();
+
+    app.execute_contract(Addr::unchecked(USER), caller, &msg, &[])
+        .unwrap();
+    assert_eq!(10, counter.count(&app.wrap()).unwrap());
+}
+
+#[test]
+fn governance_freezes_counter() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    // sudo messages come from the chain, not from an account
+    app.wasm_sudo(counter.addr(), &SudoMsg::Freeze {}).unwrap();
+
+    let msg = caller::ExecuteMsg::IncrementCounter {
+        counter: counter.addr().into(),
+    };
+    app.execute_contract(Addr::unchecked(USER), caller.clone(), &msg, &[])
+        .unwrap_err();
+    let increment = ExecuteMsg::Increment {};
+    app.execute_contract(Addr::unchecked(ADMIN), counter.addr(), &increment, &[])
+        .unwrap_err();
+
+    app.wasm_sudo(counter.addr(), &SudoMsg::Unfreeze {})
+        .unwrap();
+    app.execute_contract(Addr::unchecked(USER), caller, &msg, &[])
+        .unwrap();
+    assert_eq!(2, counter.count(&app.wrap()).unwrap());
+}
+
+#[test]
+fn governance_replaces_admin() {
+    let (mut app, counter, caller) = proper_instantiate();
+
+    let msg = SudoMsg::SetAdmin {
+        address: caller.to_string(),
+    };
+    app.wasm_sudo(counter.addr(), &msg).unwrap();
+
+    let reset = ExecuteMsg::Reset { count: 10 };
+    app.execute_contract(Addr::unchecked(ADMIN), counter.addr(), &reset, &[])
+        .unwrap_err();
+
+    let msg = caller::ExecuteMsg::ResetCounter {
+        counter: counter.addr().into(),
+        count: 10,
+    };
+    app.execute_contract(Addr::unchecked(USER), caller, &msg, &[])
+        .unwrap();
+    assert_eq!(10, counter.count(&app.wrap()).unwrap());
+
+    // governance can reset the counter without any admin
+    app.wasm_sudo(counter.addr(), &SudoMsg::ForceReset { count: 0 })
+        .unwrap();
+    assert_eq!(0, counter.count(&app.wrap()).unwrap());
+}
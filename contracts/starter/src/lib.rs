@@ -1,6 +1,14 @@
+pub mod access_control;
 pub mod contract;
 mod error;
+pub mod events;
+pub mod helpers;
+#[cfg(feature = "ibc")]
+pub mod ibc;
+mod integration_tests;
+pub mod migrations;
 pub mod msg;
+pub mod rate_limit;
 pub mod state;
 
 pub use crate::error::ContractError;
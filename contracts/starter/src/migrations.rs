@@ -0,0 +1,144 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Env, Storage};
+use cw_storage_plus::Item;
+use semver::Version;
+
+use crate::access_control::{grant_role, ADMIN_ROLE};
+use crate::error::ContractError;
+use crate::state::{State, STATE};
+
+// A migration step rewrites the stored state from the layout used before version `to`
+// into the layout expected by version `to`
+pub struct Migration {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub migrate: fn(&mut dyn Storage, &Env) -> Result<(), ContractError>,
+}
+
+// Ordered migration steps, add one for every release that changes the stored state layout
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: "0.1.0",
+    to: "0.2.0",
+    migrate: owner_to_admin_role,
+}];
+
+// State layout up to 0.1.0, with a single owner instead of the admin role
+#[cw_serde]
+struct LegacyState {
+    count: i32,
+    owner: Addr,
+}
+
+const LEGACY_STATE: Item<LegacyState> = Item::new("state");
+
+// 0.2.0 snapshots the state, adds the count bounds and hands the owner the admin role
+fn owner_to_admin_role(storage: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    let state = LEGACY_STATE.load(storage)?;
+    // the snapshot changelog cannot read the old layout, so it is replaced rather than updated
+    LEGACY_STATE.remove(storage);
+    STATE.save(
+        storage,
+        &State {
+            count: state.count,
+            min: None,
+            max: None,
+        },
+        env.block.height,
+    )?;
+    grant_role(storage, ADMIN_ROLE, &state.owner)?;
+    Ok(())
+}
+
+// Apply the steps needed to bring state written by `previous` up to `current`, in order.
+// Returns the versions that were migrated to.
+pub fn run_migrations(
+    storage: &mut dyn Storage,
+    env: &Env,
+    migrations: &[Migration],
+    previous: &Version,
+    current: &Version,
+) -> Result<Vec<&'static str>, ContractError> {
+    let mut applied = vec![];
+    for migration in migrations {
+        let from: Version = migration.from.parse()?;
+        let to: Version = migration.to.parse()?;
+        if from >= to {
+            return Err(ContractError::CustomError {
+                val: format!("Invalid migration from {} to {}", from, to),
+            });
+        }
+        if *previous < to && to <= *current {
+            (migration.migrate)(storage, env)?;
+            applied.push(migration.to);
+        }
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+
+    use crate::access_control::has_role;
+
+    fn version(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    #[test]
+    fn migrate_owner_to_admin_role() {
+        let mut storage = MockStorage::new();
+        LEGACY_STATE
+            .save(
+                &mut storage,
+                &LegacyState {
+                    count: 7,
+                    owner: Addr::unchecked("creator"),
+                },
+            )
+            .unwrap();
+        assert!(!has_role(&storage, ADMIN_ROLE, &Addr::unchecked("creator")));
+
+        let applied = run_migrations(
+            &mut storage,
+            &mock_env(),
+            MIGRATIONS,
+            &version("0.1.0"),
+            &version("0.2.0"),
+        )
+        .unwrap();
+        assert_eq!(vec!["0.2.0"], applied);
+
+        let state = STATE.load(&storage).unwrap();
+        assert_eq!(7, state.count);
+        assert!(has_role(&storage, ADMIN_ROLE, &Addr::unchecked("creator")));
+    }
+
+    #[test]
+    fn skip_applied_and_future_migrations() {
+        let mut storage = MockStorage::new();
+
+        // state is already in the 0.2.0 layout
+        let applied = run_migrations(
+            &mut storage,
+            &mock_env(),
+            MIGRATIONS,
+            &version("0.2.0"),
+            &version("0.3.0"),
+        )
+        .unwrap();
+        assert!(applied.is_empty());
+
+        // the code being migrated to predates the 0.2.0 layout
+        let applied = run_migrations(
+            &mut storage,
+            &mock_env(),
+            MIGRATIONS,
+            &version("0.0.1"),
+            &version("0.1.0"),
+        )
+        .unwrap();
+        assert!(applied.is_empty());
+    }
+}
@@ -1,27 +1,184 @@
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Addr;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+use crate::state::CounterChange;
+
+#[cw_serde]
 pub struct InstantiateMsg {
     pub count: i32,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+// Every execute returns the new count as json-encoded data
+#[cw_serde]
 pub enum ExecuteMsg {
     Increment {},
-    Reset { count: i32 },
+    Decrement {},
+    IncrementBy {
+        amount: i32,
+    },
+    Reset {
+        count: i32,
+    },
+    SetBounds {
+        min: Option<i32>,
+        max: Option<i32>,
+    },
+    // SetRateLimit limits the counter updates per address, None removes the limit
+    SetRateLimit {
+        rate_limit: Option<RateLimit>,
+    },
+    GrantRole {
+        role: String,
+        address: String,
+    },
+    RevokeRole {
+        role: String,
+        address: String,
+    },
+    RenounceRole {
+        role: String,
+    },
+    // IncrementRemote increments the counter on the other end of an IBC channel
+    #[cfg(feature = "ibc")]
+    IncrementRemote {
+        channel_id: String,
+    },
+}
+
+// A sliding window of blocks or seconds
+#[cw_serde]
+pub enum Window {
+    Blocks(u64),
+    Seconds(u64),
+}
+
+#[cw_serde]
+pub struct RateLimit {
+    // maximum number of counter updates per address within the window
+    pub limit: u32,
+    pub window: Window,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+// Messages only chain governance can send, through the `sudo` entry point
+#[cw_serde]
+pub enum SudoMsg {
+    // ForceReset sets the count without an admin, the bounds still apply
+    ForceReset { count: i32 },
+    // SetAdmin replaces all admins with `address`
+    SetAdmin { address: String },
+    // Freeze stops increments and decrements until Unfreeze
+    Freeze {},
+    Unfreeze {},
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
+#[cw_serde]
+#[derive(QueryResponses)]
 pub enum QueryMsg {
-    // GetCount returns the current count as a json-encoded number
-    GetCount {},
+    // GetCount returns the current count as a json-encoded number,
+    // or the count at the start of block `at_height`
+    #[returns(GetCountResponse)]
+    GetCount { at_height: Option<u64> },
+    // GetBounds returns the bounds on the global count
+    #[returns(BoundsResponse)]
+    GetBounds {},
+    // Changelog lists the last 100 changes to the global count, oldest first
+    #[returns(ChangelogResponse)]
+    Changelog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // GetCountOf returns the counter of a single account
+    #[returns(GetCountOfResponse)]
+    GetCountOf { address: String },
+    // ListCounts pages through the account counters ordered by address
+    #[returns(CountsResponse)]
+    ListCounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // TopCounts returns the accounts with the highest counters
+    #[returns(CountsResponse)]
+    TopCounts { limit: Option<u32> },
+    // RateLimitStatus returns how many updates `address` has left in the current window
+    #[returns(RateLimitStatusResponse)]
+    RateLimitStatus { address: String },
+    // IsFrozen returns whether governance froze the counter
+    #[returns(FrozenResponse)]
+    IsFrozen {},
+    // RemoteConfirmed returns the number of remote increments acknowledged by the counterparty
+    #[cfg(feature = "ibc")]
+    #[returns(RemoteConfirmedResponse)]
+    RemoteConfirmed {},
+    #[returns(HasRoleResponse)]
+    HasRole { role: String, address: String },
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct GetCountResponse {
     pub count: i32,
 }
+
+#[cw_serde]
+pub struct BoundsResponse {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+#[cw_serde]
+pub struct ChangelogResponse {
+    pub changes: Vec<CounterChange>,
+}
+
+#[cw_serde]
+pub struct GetCountOfResponse {
+    pub count: i64,
+}
+
+#[cw_serde]
+pub struct AccountCount {
+    pub address: Addr,
+    pub count: i64,
+}
+
+#[cw_serde]
+pub struct CountsResponse {
+    pub counts: Vec<AccountCount>,
+}
+
+#[cw_serde]
+pub struct RateLimitStatusResponse {
+    pub rate_limit: Option<RateLimit>,
+    pub used: u32,
+    // None when there is no limit
+    pub remaining: Option<u32>,
+}
+
+#[cw_serde]
+pub struct FrozenResponse {
+    pub frozen: bool,
+}
+
+#[cw_serde]
+pub struct RemoteConfirmedResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct HasRoleResponse {
+    pub has_role: bool,
+}
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub members: Vec<Addr>,
+}
@@ -0,0 +1,86 @@
+use cosmwasm_std::{Addr, Deps, Env, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+
+use crate::error::ContractError;
+use crate::msg::{RateLimit, RateLimitStatusResponse, Window};
+use crate::state::BlockStamp;
+
+// Limit on counter updates per address, unlimited when not set
+pub const RATE_LIMIT: Item<RateLimit> = Item::new("rate_limit");
+
+// Recent counter updates of each address, oldest first
+pub const RECENT_UPDATES: Map<&Addr, Vec<BlockStamp>> = Map::new("recent_updates");
+
+// Windows reaching past the largest height or time never end
+fn in_window(window: &Window, env: &Env, stamp: &BlockStamp) -> bool {
+    match window {
+        Window::Blocks(blocks) => stamp.height.saturating_add(*blocks) > env.block.height,
+        Window::Seconds(seconds) => {
+            let end = stamp
+                .time
+                .nanos()
+                .saturating_add(seconds.saturating_mul(1_000_000_000));
+            end > env.block.time.nanos()
+        }
+    }
+}
+
+// Updates of `address` that are still within the sliding window
+fn recent_updates(
+    storage: &dyn Storage,
+    env: &Env,
+    window: &Window,
+    address: &Addr,
+) -> StdResult<Vec<BlockStamp>> {
+    let mut updates = RECENT_UPDATES
+        .may_load(storage, address)?
+        .unwrap_or_default();
+    updates.retain(|stamp| in_window(window, env, stamp));
+    Ok(updates)
+}
+
+// Guard for counter updates, records the update unless `address` used up its limit
+pub fn check_rate_limit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    let rate_limit = match RATE_LIMIT.may_load(storage)? {
+        Some(rate_limit) => rate_limit,
+        None => return Ok(()),
+    };
+    let mut updates = recent_updates(storage, env, &rate_limit.window, address)?;
+    if updates.len() >= rate_limit.limit as usize {
+        return Err(ContractError::RateLimited {
+            limit: rate_limit.limit,
+        });
+    }
+    updates.push(BlockStamp {
+        height: env.block.height,
+        time: env.block.time,
+    });
+    RECENT_UPDATES.save(storage, address, &updates)?;
+    Ok(())
+}
+
+pub fn query_rate_limit_status(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<RateLimitStatusResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let rate_limit = RATE_LIMIT.may_load(deps.storage)?;
+    let (used, remaining) = match &rate_limit {
+        Some(rate_limit) => {
+            let used = recent_updates(deps.storage, &env, &rate_limit.window, &address)?.len();
+            let used = used as u32;
+            (used, Some(rate_limit.limit.saturating_sub(used)))
+        }
+        None => (0, None),
+    };
+    Ok(RateLimitStatusResponse {
+        rate_limit,
+        used,
+        remaining,
+    })
+}
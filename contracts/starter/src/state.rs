@@ -1,13 +1,46 @@
-use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Timestamp};
+use cw_storage_plus::{Item, Map, SnapshotItem, Strategy};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
-
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[cw_serde]
 pub struct State {
     pub count: i32,
-    pub owner: Addr,
+    // bounds on the global count, set by admins
+    pub min: Option<i32>,
+    pub max: Option<i32>,
 }
 
-pub const STATE: Item<State> = Item::new("state");
+#[cw_serde]
+pub struct CounterChange {
+    pub id: u64,
+    pub address: Addr,
+    pub count: i32,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct BlockStamp {
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+// snapshotted every block, so past values can be loaded by height
+pub const STATE: SnapshotItem<State> = SnapshotItem::new(
+    "state",
+    "state__checkpoints",
+    "state__changelog",
+    Strategy::EveryBlock,
+);
+
+// who changed the global counter and when, keyed by change id, only the last 100 are kept
+pub const CHANGELOG: Map<u64, CounterChange> = Map::new("changelog");
+pub const CHANGE_COUNT: Item<u64> = Item::new("change_count");
+
+// per-account counters, next to the global one in `State`
+pub const COUNTS: Map<&Addr, i64> = Map::new("counts");
+// the same counters ordered by count, kept in sync with `COUNTS`
+pub const COUNTS_BY_VALUE: Map<(i64, &Addr), ()> = Map::new("counts_by_value");
+
+// set by chain governance through `sudo`, blocks changes to the counters while true
+pub const FROZEN: Item<bool> = Item::new("frozen");